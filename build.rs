@@ -15,7 +15,7 @@ fn assets() {
     let mut file = BufWriter::new(File::create(path).unwrap());
 
     let textures = std::fs::read_dir("assets/textures").unwrap();
-    let filenames = textures
+    let mut filenames = textures
         .filter_map(|entry| entry.ok())
         .filter_map(|entry| {
             let path = entry.path();
@@ -29,6 +29,8 @@ fn assets() {
         })
         .map(|(entry, size)| (entry.file_name().into_string().unwrap(), size))
         .collect::<Vec<_>>();
+    // read_dir order differs between machines, and tile choices depend on it
+    filenames.sort_by(|(a, _), (b, _)| a.cmp(b));
 
     writeln!(
         file,
@@ -46,7 +48,7 @@ fn assets() {
     writeln!(file, "];").unwrap();
 
     let colliders = std::fs::read_dir("assets/colliders").unwrap();
-    let filenames = colliders
+    let mut filenames = colliders
         .filter_map(|entry| entry.ok())
         .filter_map(|entry| {
             let path = entry.path();
@@ -56,6 +58,7 @@ fn assets() {
             }
         })
         .collect::<Vec<_>>();
+    filenames.sort();
 
     writeln!(file, "pub const COLLIDER_FILENAMES: &[&str] = &[").unwrap();
     for filename in filenames.iter() {
@@ -64,7 +67,7 @@ fn assets() {
     writeln!(file, "];").unwrap();
 
     let levels = std::fs::read_dir("assets/levels").unwrap();
-    let filenames = levels
+    let mut filenames = levels
         .filter_map(|entry| entry.ok())
         .filter_map(|entry| {
             let path = entry.path();
@@ -74,6 +77,7 @@ fn assets() {
             }
         })
         .collect::<Vec<_>>();
+    filenames.sort();
 
     writeln!(file, "pub const LEVEL_FILENAMES: &[&str] = &[").unwrap();
     for filename in filenames.iter() {
@@ -81,8 +85,27 @@ fn assets() {
     }
     writeln!(file, "];").unwrap();
 
+    let levels = std::fs::read_dir("assets/levels").unwrap();
+    let mut filenames = levels
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let path = entry.path();
+            match path.extension().and_then(|s| s.to_str()) {
+                Some("bin") => Some(entry.file_name().into_string().unwrap()),
+                _ => None,
+            }
+        })
+        .collect::<Vec<_>>();
+    filenames.sort();
+
+    writeln!(file, "pub const LEVEL_CACHE_FILENAMES: &[&str] = &[").unwrap();
+    for filename in filenames.iter() {
+        writeln!(file, r#"    "{}","#, filename).unwrap();
+    }
+    writeln!(file, "];").unwrap();
+
     let lights = std::fs::read_dir("assets/lights").unwrap();
-    let filenames = lights
+    let mut filenames = lights
         .filter_map(|entry| entry.ok())
         .filter_map(|entry| {
             let path = entry.path();
@@ -92,6 +115,7 @@ fn assets() {
             }
         })
         .collect::<Vec<_>>();
+    filenames.sort();
 
     writeln!(file, "pub const LIGHT_FILENAMES: &[&str] = &[").unwrap();
     for filename in filenames.iter() {
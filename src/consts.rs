@@ -24,6 +24,12 @@ pub const TILE_HEIGHT: f32 = 120.0;
 pub const TILE_DOWN: f32 = 20.0;
 
 pub const CCD_ENABLED: bool = true;
+/// contact forces (N) on the player below this don't make impact events, resting is about 0.2
+pub const PLAYER_IMPACT_FORCE: f32 = 1.0;
+/// seconds polly ignores one-way platforms after dropping through
+pub const PLAYER_DROP_THROUGH_TIME: f32 = 0.3;
+/// how far (radians) from a one-way platform's up a contact can be and still block
+pub const ONE_WAY_ANGLE: f32 = std::f32::consts::FRAC_PI_3;
 pub const DEAD_ALIVE_TRANSITION_DURATION: f32 = 1.0;
 
 pub const RESPAWN_AQUIRE_RADIUS: f32 = 0.3;
@@ -35,6 +41,8 @@ pub const LAZY_PLAYER_RECT: f32 = 0.1;
 
 pub const LEVEL_X: f32 = 0.0;
 pub const LEVEL_Y: f32 = -3.0;
+/// how far below the bottom of the current level the player dies
+pub const FALL_MARGIN: f32 = 1000.0 / PIXEL_TO_METER;
 
 pub const LIGHT_FLICKER_INTERVAL: f32 = 10.0;
 pub const LIGHT_FLICKER_GROW_SPEED: f32 = 5.0;
@@ -42,6 +50,95 @@ pub const BACKGROUND_TRANSITION_SPEED: f32 = 1.0;
 
 pub const COLLISION_LAYER_PLAYER: u32 = 0b0001;
 pub const COLLISION_LAYER_ENVIRONMENT: u32 = 0b0010;
+pub const COLLISION_LAYER_PROP: u32 = 0b0100;
 pub const BAMBOO_SEGMENT_HEIGHT: f32 = 200.0;
 
 pub const FLYTRAP_TEETH_SPEED: f32 = 0.1;
+
+pub const WATER_BUOYANCY: f32 = 8.0;
+pub const WATER_BUOYANCY_DEPTH: f32 = 60.0 / PIXEL_TO_METER;
+pub const WATER_SURFACE_SPACING: f32 = 20.0 / PIXEL_TO_METER;
+pub const WATER_SURFACE_TOLERANCE: f32 = 10.0 / PIXEL_TO_METER;
+pub const WATER_SPRING_STIFFNESS: f32 = 60.0;
+pub const WATER_SPRING_DAMPING: f32 = 2.0;
+pub const WATER_SPREAD: f32 = 900.0;
+pub const WATER_SUBSTEP: f32 = 1.0 / 120.0;
+pub const WATER_SPLASH_RADIUS: f32 = 100.0 / PIXEL_TO_METER;
+pub const WATER_SPLASH_STRENGTH: f32 = 0.3;
+pub const WATER_WAKE_STRENGTH: f32 = 0.4;
+pub const WATER_TINT_DEPTH: f32 = 40.0 / PIXEL_TO_METER;
+/// props lighter than this float
+pub const WATER_DENSITY: f32 = 1.0;
+pub const WATER_DRAG: f32 = 0.5;
+
+pub const PROP_CRATE_DENSITY: f32 = 0.6;
+pub const PROP_LOG_DENSITY: f32 = 0.5;
+pub const PROP_BOULDER_DENSITY: f32 = 2.5;
+
+pub const GATE_OPEN_SPEED: f32 = 1.5;
+pub const LEVER_ANGLE: f32 = 0.5;
+pub const SAVE_KEY: &str = "save";
+pub const SPLITS_KEY: &str = "splits";
+pub const PROGRESS_KEY: &str = "progress";
+
+pub const WIND_COLOR: u32 = 0xB5DCE8;
+pub const WIND_ROLLY_FACTOR: f32 = 1.0;
+pub const WIND_POLLY_FACTOR: f32 = 0.35;
+pub const WIND_PARTICLE_SPEED: f32 = 0.4;
+pub const WIND_PARTICLE_DENSITY: f32 = 1.5;
+pub const WIND_LEAF_CHANCE: f32 = 0.2;
+pub const WIND_LEAF_SPIN: f32 = 4.0;
+
+pub const COLLECTIBLE_COLOR: u32 = 0xE0475B;
+/// marker circle other levels can link to, named by `data-door`
+pub const DOOR_COLOR: u32 = 0xFFB000;
+pub const COLLECTIBLE_PICKUP_RADIUS: f32 = 0.15;
+
+pub const SPEEDRUN_SHOWN_SPLITS: usize = 5;
+pub const GHOST_ALPHA: f32 = 0.35;
+/// seconds between recorded ghost frames, poses in between are interpolated
+pub const GHOST_FRAME_INTERVAL: f32 = 1.0 / 30.0;
+/// once a recording has this many frames every other frame is dropped
+pub const GHOST_MAX_FRAMES: usize = 18000;
+
+/// half height of the band the player can move in without the camera following vertically
+pub const CAMERA_DEAD_ZONE: f32 = 0.6;
+pub const CAMERA_RECENTER_SPEED: f32 = 3.0;
+/// seconds of horizontal velocity the camera looks ahead
+pub const CAMERA_LOOK_AHEAD: f32 = 0.3;
+pub const CAMERA_LOOK_AHEAD_MAX: f32 = 1.2;
+pub const CAMERA_LOOK_AHEAD_SPEED: f32 = 2.0;
+pub const CAMERA_ZOOM_SPEED: f32 = 2.0;
+pub const CAMERA_SHAKE_DECAY: f32 = 1.5;
+pub const CAMERA_SHAKE_FREQUENCY: f32 = 40.0;
+pub const CAMERA_SHAKE_OFFSET: f32 = 0.15;
+pub const CAMERA_SHAKE_ROTATION: f32 = 2.0;
+pub const CAMERA_SHAKE_MUSHROOM: f32 = 0.3;
+pub const CAMERA_SHAKE_FLYTRAP: f32 = 0.4;
+/// impulse (N·s) of a hit that starts shaking the camera, about a landing at 6 m/s
+pub const CAMERA_IMPACT_IMPULSE: f32 = 0.1;
+pub const CAMERA_IMPACT_SHAKE: f32 = 5.0;
+
+/// how much further than its glow a light reaches in the light map
+pub const LIGHT_MAP_RADIUS: f32 = 5.0;
+pub const LIGHT_MAP_FALLOFF: f32 = 1.5;
+/// the light map packs falloff into vertex alpha, so it can't go higher
+pub const LIGHT_MAP_MAX_FALLOFF: f32 = 4.0;
+/// lights still light up their surroundings a little while they are off
+pub const LIGHT_MAP_IDLE_STRENGTH: f32 = 0.3;
+pub const PLAYER_LIGHT_RADIUS: f32 = 2.0;
+pub const PLAYER_LIGHT_COLOR: u32 = 0xFFE9C4;
+
+pub const MINIMAP_WIDTH: f32 = 280.0;
+pub const MINIMAP_HEIGHT: f32 = 140.0;
+/// meters of the world shown across the minimap
+pub const MINIMAP_RANGE: f32 = 40.0;
+pub const MINIMAP_SIMPLIFY: f32 = 30.0 / PIXEL_TO_METER;
+
+/// view widths per second
+pub const PHOTO_PAN_SPEED: f32 = 0.5;
+pub const PHOTO_ZOOM_STEP: f32 = 1.1;
+pub const PHOTO_MIN_ZOOM: f32 = 0.02;
+pub const PHOTO_MAX_ZOOM: f32 = 4.0;
+
+pub const LEVEL_RENDER_MAX_SIZE: f32 = 8192.0;
@@ -1,14 +1,31 @@
 pub mod assets;
 pub mod config;
+pub mod photo;
+pub mod progress;
 pub mod ui;
 pub mod world;
 
+use std::collections::HashMap;
+
+use crate::consts::SAVE_KEY;
 use crate::game::config::GameConfig;
 
-use self::{assets::Assets, ui::settings::Settings};
+use self::{
+    assets::Assets,
+    photo::PhotoMode,
+    progress::Progress,
+    ui::{level_select::LevelSelect, settings::Settings},
+};
 use macroquad::prelude::*;
+use nanoserde::{DeJson, SerJson};
 
-use world::{World, draw::draw as draw_world, update::update as update_world};
+use world::{
+    World,
+    draw::{draw as draw_world, draw_with},
+    update::update as update_world,
+};
+#[cfg(not(target_arch = "wasm32"))]
+use world::{draw::render_level, level::LevelId, level_cache, stream::PreparedLevel};
 
 pub struct Game {
     pub assets: Assets,
@@ -16,6 +33,10 @@ pub struct Game {
     pub screen: Screen,
     pub world: Option<World>,
     pub config: GameConfig,
+    pub level_select: Option<LevelSelect>,
+    pub photo: Option<PhotoMode>,
+    /// whether there is a saved world to continue, checked when the home screen opens
+    pub has_save: bool,
 }
 
 impl Game {
@@ -28,6 +49,9 @@ impl Game {
             screen: Screen::Home,
             world: None,
             config,
+            level_select: None,
+            photo: None,
+            has_save: SavedWorld::load().is_some(),
         }
     }
     pub fn quit(&self) -> bool {
@@ -44,25 +68,110 @@ pub async fn tick(game: &mut Game) {
         if let Screen::Running = game.screen {
             update_world(&mut game.assets, &game.settings, world, &game.config).await;
         }
-        draw_world(&game.settings, &game.assets, world);
+        match &game.photo {
+            Some(photo) => draw_with(&game.settings, &game.assets, world, &photo.options()),
+            None => draw_world(&game.settings, &game.assets, world),
+        }
     }
-    // if requested to quit, save world
+    // if requested to quit, end the run and save world
     if is_quit_requested() {
+        if let Some(world) = &mut game.world {
+            world.speedrun.finish();
+        }
         save_world(game);
     }
     ui::tick(game);
 }
 
-fn save_world(_game: &mut Game) {}
+/// Renders every level to `dir/level_<n>.png`.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn export_levels(assets: &Assets, dir: &str, scale: f32) {
+    std::fs::create_dir_all(dir).unwrap();
+    let mut levels = assets.levels.keys().copied().collect::<Vec<_>>();
+    levels.sort_unstable();
+    for level in levels {
+        let target = render_level(assets, LevelId(level), scale);
+        let path = format!("{}/level_{}.png", dir, level);
+        target.texture.get_texture_data().export_png(&path);
+        target.texture.delete();
+        println!("Exported {}", path);
+    }
+}
+
+/// Compiles every level svg to `assets/levels/<n>.bin`, which loads instead of the svg until
+/// the svg or tiles change.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn compile_levels(assets: &Assets) {
+    let mut levels = assets.levels.keys().copied().collect::<Vec<_>>();
+    levels.sort_unstable();
+    for level in levels {
+        let (info, svg) = &assets.levels[&level];
+        let prepared = PreparedLevel::new(&assets.tiles, svg);
+        let bytes = level_cache::encode(svg, &assets.tiles, info, &prepared);
+        let path = format!("assets/levels/{}.bin", level);
+        std::fs::write(&path, bytes).unwrap();
+        println!("Compiled {}", path);
+    }
+}
 
-pub struct SavedWorld {}
+fn save_world(game: &mut Game) {
+    if let Some(world) = &game.world {
+        SavedWorld::from_world(world).store();
+        Progress::record(world);
+    }
+}
+
+/// Progress kept between sessions.
+#[derive(Debug, Clone, SerJson, DeJson)]
+pub struct SavedWorld {
+    /// (level, thing) of the current respawn
+    pub respawn: (usize, usize),
+    pub respawns: Vec<(usize, usize)>,
+    pub signals: HashMap<String, bool>,
+    /// (level, thing) of every collectible picked up
+    pub collected: Vec<(usize, usize)>,
+}
+
+impl SavedWorld {
+    pub fn from_world(world: &World) -> Self {
+        let (level, thing) = world.respawns().respawn();
+        Self {
+            respawn: (level.0, thing.0),
+            respawns: world
+                .respawns()
+                .all_respawns()
+                .iter()
+                .map(|(level, thing)| (level.0, thing.0))
+                .collect(),
+            signals: world.signals.0.clone(),
+            collected: world
+                .collected
+                .iter()
+                .map(|(level, thing)| (level.0, thing.0))
+                .collect(),
+        }
+    }
+    pub fn load() -> Option<Self> {
+        let json = quad_storage::STORAGE.lock().unwrap().get(SAVE_KEY)?;
+        // an unreadable save is treated like no save
+        SavedWorld::deserialize_json(&json).ok()
+    }
+    pub fn store(&self) {
+        quad_storage::STORAGE
+            .lock()
+            .unwrap()
+            .set(SAVE_KEY, &self.serialize_json());
+    }
+}
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Screen {
     Home,
+    LevelSelect,
     Settings,
     SettingsPaused,
     Paused,
+    Photo,
     Running,
     Quit,
 }
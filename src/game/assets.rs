@@ -1,18 +1,29 @@
 use macroquad::prelude::*;
 use rustc_hash::FxHashMap as HashMap;
 use std::ops::RangeInclusive;
+use std::sync::Arc;
 
 use crate::game::world::draw::pixel_to_meter;
 
+use super::world::draw::light_map::LightMap;
 use super::world::level::LevelInfo;
+use super::world::level_cache;
+use super::world::level_graph::LevelGraph;
+use super::world::stream::PreparedLevel;
 
 type SizedTexture = ((usize, usize), Texture2D);
+pub type Tiles = HashMap<String, Tile>;
 pub struct Assets {
     pub textures: HashMap<String, SizedTexture>,
-    pub tiles: HashMap<String, Tile>,
+    pub tiles: Tiles,
     pub colliders: HashMap<String, String>,
     pub levels: HashMap<usize, (LevelInfo, String)>,
+    /// levels compiled ahead of time that still match their svg
+    pub level_cache: HashMap<usize, Arc<PreparedLevel>>,
+    pub level_graph: LevelGraph,
     pub lights: HashMap<String, String>,
+    /// light map of the last world, reused by the next one since render targets are never freed
+    pub light_map: Option<LightMap>,
 }
 
 include!(concat!(env!("OUT_DIR"), "/asset_codegen.rs"));
@@ -32,14 +43,7 @@ impl Assets {
             texture.set_filter(FilterMode::Nearest);
             texture
         });
-        let texture_name_and_args = TEXTURE_FILENAMES
-            .iter()
-            .map(|(filename, size)| {
-                let filename = filename.strip_suffix(".png").expect("expected png image");
-                let (name, args) = split_name_args(filename);
-                (name, *size, args)
-            })
-            .collect::<Vec<_>>();
+        let texture_name_and_args = texture_name_and_args();
         let tiles = extract_tiles(&texture_name_and_args);
         let textures = texture_name_and_args
             .iter()
@@ -91,30 +95,57 @@ impl Assets {
         for filename in level_paths {
             levels_load.push(load_string(&filename).await);
         }
-        let levels = LEVEL_FILENAMES
+        let level_names = LEVEL_FILENAMES
             .iter()
             .map(|filename| {
                 let filename = filename.strip_suffix(".svg").expect("expected svg file");
                 let (name, _) = split_name_args(filename);
                 name.parse().unwrap()
             })
-            .zip(
-                levels_load
-                    .into_iter()
-                    .map(|level| level.unwrap())
-                    .map(|level| {
-                        let level_info = LevelInfo::parse(&level);
-                        (level_info, level)
-                    }),
-            )
-            .collect::<HashMap<_, _>>();
+            .zip(levels_load.into_iter().map(|level| level.unwrap()))
+            .collect::<Vec<(usize, String)>>();
+
+        // the svg is only parsed when a level has no cache or it's stale
+        let mut level_caches = HashMap::default();
+        for filename in LEVEL_CACHE_FILENAMES {
+            let name = filename.strip_suffix(".bin").expect("expected bin file");
+            let (name, _) = split_name_args(name);
+            let level: usize = name.parse().unwrap();
+            if let Ok(bytes) = load_file(&format!("assets/levels/{}", filename)).await {
+                level_caches.insert(level, bytes);
+            }
+        }
+        let mut levels = HashMap::default();
+        let mut level_cache = HashMap::default();
+        for (level, svg) in level_names {
+            let cached = level_caches
+                .get(&level)
+                .and_then(|bytes| level_cache::decode(bytes, &svg, &tiles));
+            let level_info = match cached {
+                Some((level_info, prepared)) => {
+                    level_cache.insert(level, Arc::new(prepared));
+                    level_info
+                }
+                None => LevelInfo::parse(&svg),
+            };
+            levels.insert(level, (level_info, svg));
+        }
+        // without a graph every level leads to the next one
+        let level_graph = match load_string("assets/levels/graph.txt").await {
+            Ok(graph) => LevelGraph::parse(&graph),
+            Err(_) => LevelGraph::linear(&levels.keys().copied().collect::<Vec<_>>()),
+        };
+        level_graph.validate(|level| levels.get(&level.0).map(|(info, _)| info));
 
         Self {
             textures,
             tiles,
             colliders,
             levels,
+            level_cache,
+            level_graph,
             lights,
+            light_map: None,
         }
     }
 }
@@ -149,6 +180,23 @@ impl std::ops::IndexMut<&str> for Assets {
     }
 }
 
+fn texture_name_and_args() -> Vec<(&'static str, (usize, usize), Option<&'static str>)> {
+    TEXTURE_FILENAMES
+        .iter()
+        .map(|(filename, size)| {
+            let filename = filename.strip_suffix(".png").expect("expected png image");
+            let (name, args) = split_name_args(filename);
+            (name, *size, args)
+        })
+        .collect()
+}
+
+/// Tiles from the texture filenames alone, without loading any textures.
+#[cfg(test)]
+pub fn texture_tiles() -> Tiles {
+    extract_tiles(&texture_name_and_args())
+}
+
 pub fn extract_tiles(
     name_and_args: &Vec<(&str, (usize, usize), Option<&str>)>,
 ) -> HashMap<String, Tile> {
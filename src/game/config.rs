@@ -12,6 +12,15 @@ pub struct GameConfig {
     #[argh(option)]
     /// level to start the game on
     pub(crate) level: Option<usize>,
+    #[argh(option)]
+    /// write a png of every level into this directory instead of starting the game
+    pub(crate) export_levels: Option<String>,
+    #[argh(option)]
+    /// png pixels per svg pixel for exported levels, 0.25 by default
+    pub(crate) export_scale: Option<f32>,
+    #[argh(switch)]
+    /// write the compiled level cache next to every level svg instead of starting the game
+    pub(crate) compile_levels: bool,
 }
 
 #[cfg(target_arch = "wasm32")]
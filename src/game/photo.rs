@@ -0,0 +1,167 @@
+use macroquad::prelude::*;
+
+use crate::consts::*;
+
+use super::assets::Assets;
+use super::ui::settings::Settings;
+use super::world::World;
+use super::world::back::Back;
+use super::world::draw::{DrawOptions, draw_with};
+use super::world::level::LevelId;
+
+/// Frozen world with a free camera. The camera and background are restored when leaving.
+pub struct PhotoMode {
+    saved_camera: Camera2D,
+    saved_back: Back,
+    pub hide_hud: bool,
+    pub hide_player: bool,
+    /// background blended in over the one of the current level
+    pub back_level: LevelId,
+    pub back_fade: f32,
+    /// screenshot size as a multiple of the screen size
+    pub scale: f32,
+    pub last_screenshot: Option<String>,
+    last_mouse: Option<Vec2>,
+}
+
+impl PhotoMode {
+    pub fn new(world: &World) -> Self {
+        Self {
+            saved_camera: world.camera,
+            saved_back: world.back.clone(),
+            hide_hud: true,
+            hide_player: false,
+            back_level: world.back.target,
+            back_fade: 0.0,
+            scale: 2.0,
+            last_screenshot: None,
+            last_mouse: None,
+        }
+    }
+
+    pub fn restore(&self, world: &mut World) {
+        world.camera = self.saved_camera;
+        world.back = self.saved_back.clone();
+    }
+
+    pub fn options(&self) -> DrawOptions {
+        DrawOptions {
+            hud: !self.hide_hud,
+            player: !self.hide_player,
+        }
+    }
+
+    /// Pans with the arrow keys, wasd or by dragging, and zooms with the mouse wheel or +/-.
+    /// `pointer_free` is false while the mouse is over the photo mode window.
+    pub fn update(&mut self, world: &mut World, pointer_free: bool) {
+        let dt = get_frame_time();
+        let camera = &mut world.camera;
+        // half the width of the view in meters
+        let view = 1.0 / camera.zoom.x;
+        let mut pan = Vec2::ZERO;
+        for (keys, dir) in [
+            ([KeyCode::Left, KeyCode::A], vec2(-1.0, 0.0)),
+            ([KeyCode::Right, KeyCode::D], vec2(1.0, 0.0)),
+            ([KeyCode::Up, KeyCode::W], vec2(0.0, -1.0)),
+            ([KeyCode::Down, KeyCode::S], vec2(0.0, 1.0)),
+        ] {
+            if keys.into_iter().any(is_key_down) {
+                pan += dir;
+            }
+        }
+        camera.target += pan * view * PHOTO_PAN_SPEED * dt;
+
+        let mouse = Vec2::from(mouse_position());
+        if pointer_free && is_mouse_button_down(MouseButton::Left) {
+            if let Some(last_mouse) = self.last_mouse {
+                camera.target -= (mouse - last_mouse) / screen_width() * view * 2.0;
+            }
+            self.last_mouse = Some(mouse);
+        } else {
+            self.last_mouse = None;
+        }
+
+        let mut zoom = 1.0;
+        let wheel = mouse_wheel().1;
+        if pointer_free && wheel != 0.0 {
+            zoom *= PHOTO_ZOOM_STEP.powf(wheel.signum());
+        }
+        if is_key_down(KeyCode::Equal) {
+            zoom *= 1.0 + dt;
+        }
+        if is_key_down(KeyCode::Minus) {
+            zoom /= 1.0 + dt;
+        }
+        let zoom_x = (camera.zoom.x * zoom).clamp(PHOTO_MIN_ZOOM, PHOTO_MAX_ZOOM);
+        camera.zoom *= zoom_x / camera.zoom.x;
+        camera.rotation = 0.0;
+
+        world.back = Back {
+            fade: self.back_fade,
+            previous: Some(self.saved_back.target),
+            target: self.back_level,
+        };
+    }
+
+    /// Renders the world at `scale` times the screen size and saves it.
+    pub fn screenshot(&mut self, settings: &Settings, assets: &Assets, world: &mut World) {
+        let size = vec2(screen_width(), screen_height()) * self.scale;
+        let size = size * (LEVEL_RENDER_MAX_SIZE / size.max_element()).min(1.0);
+        let target = render_target(size.x as u32, size.y as u32);
+        let camera = world.camera;
+        world.camera.render_target = Some(target);
+        // the hud is drawn straight to the screen, so it is never part of the screenshot
+        let options = DrawOptions {
+            hud: false,
+            ..self.options()
+        };
+        draw_with(settings, assets, world, &options);
+        world.camera = camera;
+        set_default_camera();
+        let image = target.texture.get_texture_data();
+        target.texture.delete();
+        self.last_screenshot = Some(save_screenshot(&image));
+    }
+}
+
+/// Writes the screenshot to `screenshots/`, returning where it went.
+#[cfg(not(target_arch = "wasm32"))]
+fn save_screenshot(image: &Image) -> String {
+    let time = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    std::fs::create_dir_all("screenshots").unwrap();
+    let path = format!("screenshots/rolly-polly-{}.png", time);
+    image.export_png(&path);
+    path
+}
+
+#[cfg(target_arch = "wasm32")]
+unsafe extern "C" {
+    /// see web/screenshot.js
+    fn screenshot_download(pixels: *const u8, len: usize, width: u32, height: u32);
+}
+
+/// Hands the screenshot to the browser as a download.
+#[cfg(target_arch = "wasm32")]
+fn save_screenshot(image: &Image) -> String {
+    // render targets are stored bottom row first
+    let row = image.width as usize * 4;
+    let pixels = image
+        .bytes
+        .chunks_exact(row)
+        .rev()
+        .flatten()
+        .copied()
+        .collect::<Vec<u8>>();
+    unsafe {
+        screenshot_download(
+            pixels.as_ptr(),
+            pixels.len(),
+            image.width as u32,
+            image.height as u32,
+        );
+    }
+    "download".to_owned()
+}
@@ -0,0 +1,52 @@
+use std::collections::HashSet;
+
+use nanoserde::{DeJson, SerJson};
+
+use crate::consts::PROGRESS_KEY;
+
+use super::world::World;
+
+/// What the player has reached over every game, used to unlock levels.
+#[derive(Debug, Clone, Default, SerJson, DeJson)]
+pub struct Progress {
+    pub reached: HashSet<usize>,
+    /// (level, thing) of every collectible ever picked up
+    pub collected: HashSet<(usize, usize)>,
+}
+
+impl Progress {
+    pub fn load() -> Self {
+        quad_storage::STORAGE
+            .lock()
+            .unwrap()
+            .get(PROGRESS_KEY)
+            .and_then(|json| Progress::deserialize_json(&json).ok())
+            .unwrap_or_default()
+    }
+    pub fn store(&self) {
+        quad_storage::STORAGE
+            .lock()
+            .unwrap()
+            .set(PROGRESS_KEY, &self.serialize_json());
+    }
+    /// Adds what was reached in `world` to the stored progress.
+    pub fn record(world: &World) {
+        let mut progress = Self::load();
+        progress
+            .reached
+            .extend(world.reached.iter().map(|level| level.0));
+        progress.collected.extend(
+            world
+                .collected
+                .iter()
+                .map(|(level, thing)| (level.0, thing.0)),
+        );
+        progress.store();
+    }
+    pub fn unlocked(&self, level: usize) -> bool {
+        level == 0 || self.reached.contains(&level)
+    }
+    pub fn collected_in(&self, level: usize) -> usize {
+        self.collected.iter().filter(|(l, _)| *l == level).count()
+    }
+}
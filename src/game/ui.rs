@@ -3,12 +3,14 @@ use egui_macroquad::egui::{Context, FontId, TextStyle};
 use egui_macroquad::{egui, egui::Ui};
 use macroquad::prelude::*;
 
+pub mod level_select;
 pub mod settings;
 
 use self::settings::SettingKindMut;
 
-use super::world::World;
-use super::{Game, Screen, save_world};
+use super::world::{World, level::LevelId};
+use super::{Game, SavedWorld, Screen, photo::PhotoMode, save_world};
+use level_select::LevelSelect;
 use settings::{Setting, SettingInfo, Settings, Slider, Toggle};
 
 const MARGIN: f32 = 10.0;
@@ -114,16 +116,29 @@ fn basic_window(f: impl FnOnce(&mut Ui)) {
 pub fn tick(game: &mut Game) {
     match game.screen {
         Screen::Home => home(game),
+        Screen::LevelSelect => level_select(game),
         Screen::Settings => settings(game, false),
         Screen::SettingsPaused => settings(game, true),
         Screen::Paused => paused(game),
+        Screen::Photo => photo(game),
         Screen::Running => running(game),
         Screen::Quit => quit(game),
     }
 }
 
 fn home(game: &mut Game) {
+    let has_save = game.has_save;
     basic_window(|ui| {
+        if has_save {
+            draw_buttons(
+                game,
+                ui,
+                &[("continue", |game| {
+                    continue_world(game);
+                    game.screen = Screen::Running;
+                })],
+            );
+        }
         draw_buttons(
             game,
             ui,
@@ -132,6 +147,7 @@ fn home(game: &mut Game) {
                     new_world(game);
                     game.screen = Screen::Running;
                 }),
+                ("levels", |game| change_screen(game, Screen::LevelSelect)),
                 ("settings", |game| change_screen(game, Screen::Settings)),
                 ("quit", |game| change_screen(game, Screen::Quit)),
             ],
@@ -143,16 +159,51 @@ fn new_world(game: &mut Game) {
     game.world = Some(World::new(&game.settings, &mut game.assets, &game.config));
 }
 
+fn level_select(game: &mut Game) {
+    if is_key_pressed(KeyCode::Escape) {
+        change_screen(game, Screen::Home);
+        return;
+    }
+    let level_select = game
+        .level_select
+        .take()
+        .unwrap_or_else(|| LevelSelect::new(&game.assets));
+    let mut picked = None;
+    basic_window(|ui| {
+        picked = level_select.show(ui);
+        draw_buttons(
+            game,
+            ui,
+            &[("back", |game| change_screen(game, Screen::Home))],
+        );
+    });
+    game.level_select = Some(level_select);
+    if let Some(level) = picked {
+        game.world = Some(World::at_level(
+            &game.settings,
+            &mut game.assets,
+            &game.config,
+            LevelId(level),
+        ));
+        change_screen(game, Screen::Running);
+    }
+}
+
+fn continue_world(game: &mut Game) {
+    let saved = SavedWorld::load().unwrap();
+    game.world = Some(World::from_save(&game.settings, &mut game.assets, &saved));
+}
+
 fn draw_label(ui: &mut Ui, setting: &Setting<impl SettingInfo>) {
     egui::Frame::none()
         .inner_margin(egui::Margin::symmetric(0.0, MARGIN))
         .show(ui, |ui| ui.add(egui::Label::new(setting.name)));
 }
 fn draw_reset_button(ui: &mut Ui, setting: &mut Setting<impl SettingInfo>) {
-    if setting.value != setting.info.default_value()
-        && ui.add(egui::Button::new("reset")).clicked() {
-            setting.value = setting.info.default_value();
-        }
+    if setting.value != setting.info.default_value() && ui.add(egui::Button::new("reset")).clicked()
+    {
+        setting.value = setting.info.default_value();
+    }
 }
 
 fn draw_slider(ui: &mut Ui, setting: &mut Setting<Slider>) {
@@ -277,6 +328,7 @@ fn paused(game: &mut Game) {
                 ("settings", |game| {
                     change_screen(game, Screen::SettingsPaused)
                 }),
+                ("photo mode", |game| change_screen(game, Screen::Photo)),
                 ("save", |game| save_world(game)),
                 ("save & back", |game| change_screen(game, Screen::Home)),
             ],
@@ -284,10 +336,79 @@ fn paused(game: &mut Game) {
     });
 }
 
+fn photo(game: &mut Game) {
+    if is_key_pressed(KeyCode::Escape) {
+        change_screen(game, Screen::Paused);
+        return;
+    }
+    let (Some(photo), Some(world)) = (&mut game.photo, &mut game.world) else {
+        return;
+    };
+    let mut screenshot = false;
+    let mut back = false;
+    let mut pointer_free = true;
+    egui_macroquad::ui(|egui_ctx| {
+        egui::Window::new("photo mode")
+            .anchor(egui::Align2::LEFT_TOP, egui::vec2(MARGIN, MARGIN))
+            .resizable(false)
+            .show(egui_ctx, |ui| {
+                ui.label("drag or wasd to pan, scroll or +/- to zoom");
+                ui.checkbox(&mut photo.hide_hud, "hide hud");
+                ui.checkbox(&mut photo.hide_player, "hide player");
+                ui.horizontal(|ui| {
+                    if ui.button("<").clicked() && photo.back_level.0 > 0 {
+                        photo.back_level.0 -= 1;
+                    }
+                    ui.label(format!("background of level {}", photo.back_level.0));
+                    if ui.button(">").clicked()
+                        && game.assets.levels.contains_key(&(photo.back_level.0 + 1))
+                    {
+                        photo.back_level.0 += 1;
+                    }
+                });
+                ui.add(egui::Slider::new(&mut photo.back_fade, 0.0..=1.0).text("blend"));
+                ui.add(egui::Slider::new(&mut photo.scale, 1.0..=4.0).text("size"));
+                screenshot = ui.button("screenshot").clicked();
+                if let Some(path) = &photo.last_screenshot {
+                    ui.label(format!("saved {}", path));
+                }
+                back = ui.button("back").clicked();
+            });
+        pointer_free = !egui_ctx.wants_pointer_input();
+    });
+    egui_macroquad::draw();
+    photo.update(world, pointer_free);
+    if screenshot {
+        photo.screenshot(&game.settings, &game.assets, world);
+    }
+    if back {
+        change_screen(game, Screen::Paused);
+    }
+}
+
 fn change_screen(game: &mut Game, screen: Screen) {
+    if let (Screen::Photo, Some(world)) = (screen, &game.world) {
+        game.photo = Some(PhotoMode::new(world));
+    }
+    if let (Screen::Photo, Some(photo), Some(world)) =
+        (game.screen, game.photo.take(), &mut game.world)
+    {
+        photo.restore(world);
+    }
     if let (Screen::Running | Screen::Paused, Screen::Home | Screen::Quit) = (game.screen, screen) {
         save_world(game);
-        game.world = None;
+        if let Some(mut world) = game.world.take() {
+            world.speedrun.finish();
+            game.assets.light_map = world.light_map;
+        }
+    }
+    // loading the save is slow, so only check for one when coming home
+    if let Screen::Home = screen {
+        game.has_save = SavedWorld::load().is_some();
+    }
+    // thumbnails are kept, but progress may have changed since the screen was last open
+    if let (Screen::LevelSelect, Some(level_select)) = (screen, &mut game.level_select) {
+        level_select.refresh();
     }
     game.screen = screen;
 }
@@ -312,7 +433,38 @@ fn running(game: &mut Game) {
     if is_key_pressed(KeyCode::Key0) {
         settings.zoom.value = 1.0;
     }
+    if is_key_pressed(KeyCode::M) {
+        settings.minimap.value = !settings.minimap.value;
+    }
+    if is_key_pressed(KeyCode::F3) {
+        settings.debug_overlay.value = !settings.debug_overlay.value;
+    }
     constrain_slider(&mut settings.zoom);
+    if let (true, Some(world)) = (settings.debug_overlay.value, &game.world) {
+        debug_overlay(world);
+    }
+}
+
+/// Frame rate and how long each system of the last frames took.
+fn debug_overlay(world: &World) {
+    egui_macroquad::ui(|egui_ctx| {
+        egui::Window::new("debug")
+            .anchor(egui::Align2::RIGHT_TOP, egui::vec2(-MARGIN, MARGIN))
+            .resizable(false)
+            .show(egui_ctx, |ui| {
+                ui.label(format!("{} fps", get_fps()));
+                let total: f64 = world.schedule.timings().map(|(_, time)| time).sum();
+                ui.label(format!("systems {:.2} ms", total * 1000.0));
+                egui::Grid::new("systems").show(ui, |ui| {
+                    for (name, time) in world.schedule.timings() {
+                        ui.label(name);
+                        ui.label(format!("{:.3}", time * 1000.0));
+                        ui.end_row();
+                    }
+                });
+            });
+    });
+    egui_macroquad::draw();
 }
 
 fn quit(_game: &mut Game) {}
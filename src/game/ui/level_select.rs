@@ -0,0 +1,134 @@
+use egui_macroquad::egui::{self, Color32, TextureId, Ui};
+use macroquad::prelude::*;
+
+use crate::game::assets::Assets;
+use crate::game::progress::Progress;
+use crate::game::world::draw::{meter_to_pixel, render_level};
+use crate::game::world::level::LevelId;
+use crate::game::world::speedrun::{SplitRecords, format_time};
+
+const THUMBNAIL_WIDTH: f32 = 160.0;
+/// render pixels per ui point, so thumbnails stay sharp on high dpi screens
+const THUMBNAIL_RESOLUTION: f32 = 2.0;
+
+/// Level rendered into a texture.
+pub struct Thumbnail {
+    pub aspect: f32,
+    pub target: RenderTarget,
+}
+
+impl Thumbnail {
+    pub fn new(assets: &Assets, level: usize) -> Self {
+        let dims = assets.levels[&level].0.dims;
+        let scale = THUMBNAIL_WIDTH * THUMBNAIL_RESOLUTION / meter_to_pixel(dims.x);
+        Self {
+            aspect: dims.y / dims.x,
+            target: render_level(assets, LevelId(level), scale),
+        }
+    }
+
+    pub fn show(&self, ui: &mut Ui, locked: bool) {
+        let size = egui::vec2(THUMBNAIL_WIDTH, THUMBNAIL_WIDTH * self.aspect);
+        let texture = self.target.texture.raw_miniquad_texture_handle();
+        let tint = if locked {
+            Color32::from_gray(50)
+        } else {
+            Color32::WHITE
+        };
+        // render targets are stored bottom row first
+        let uv = egui::Rect::from_min_max(egui::pos2(0.0, 1.0), egui::pos2(1.0, 0.0));
+        ui.add(
+            egui::Image::new(TextureId::User(texture.gl_internal_id() as u64), size)
+                .uv(uv)
+                .tint(tint),
+        );
+    }
+}
+
+pub struct LevelEntry {
+    pub level: usize,
+    /// levels the graph leads on to, whose segments end this level
+    pub next: Vec<usize>,
+    pub thumbnail: Thumbnail,
+    pub unlocked: bool,
+    /// fastest segment through this level
+    pub best: Option<f64>,
+    pub collected: usize,
+    pub collectibles: usize,
+}
+
+/// Everything shown on the level select screen. Thumbnails are rendered once, the rest is
+/// gathered again with `refresh` whenever the screen is opened.
+pub struct LevelSelect {
+    pub entries: Vec<LevelEntry>,
+}
+
+impl LevelSelect {
+    pub fn new(assets: &Assets) -> Self {
+        let mut levels = assets.levels.keys().copied().collect::<Vec<_>>();
+        levels.sort_unstable();
+        let entries = levels
+            .into_iter()
+            .map(|level| LevelEntry {
+                level,
+                next: assets
+                    .level_graph
+                    .next_levels(LevelId(level))
+                    .map(|next| next.0)
+                    .collect(),
+                thumbnail: Thumbnail::new(assets, level),
+                unlocked: false,
+                best: None,
+                collected: 0,
+                collectibles: assets.levels[&level].0.collectibles,
+            })
+            .collect();
+        let mut level_select = Self { entries };
+        level_select.refresh();
+        level_select
+    }
+
+    pub fn refresh(&mut self) {
+        let progress = Progress::load();
+        let records = SplitRecords::load();
+        for entry in self.entries.iter_mut() {
+            entry.unlocked = progress.unlocked(entry.level);
+            // segments are keyed by the link they cross, so take the fastest way out
+            entry.best = entry
+                .next
+                .iter()
+                .filter_map(|next| records.gold(LevelId(entry.level), LevelId(*next)))
+                .min_by(f64::total_cmp);
+            entry.collected = progress.collected_in(entry.level);
+        }
+    }
+
+    /// Draws the list, returning the level to start if one was picked.
+    pub fn show(&self, ui: &mut Ui) -> Option<usize> {
+        let mut picked = None;
+        for entry in self.entries.iter() {
+            ui.horizontal(|ui| {
+                entry.thumbnail.show(ui, !entry.unlocked);
+                ui.vertical(|ui| {
+                    ui.label(format!("level {}", entry.level));
+                    if !entry.unlocked {
+                        ui.label("locked");
+                        return;
+                    }
+                    let best = entry.best.map_or("--".to_owned(), format_time);
+                    ui.label(format!("best {}", best));
+                    if entry.collectibles > 0 {
+                        ui.label(format!(
+                            "berries {} / {}",
+                            entry.collected, entry.collectibles
+                        ));
+                    }
+                    if ui.button("play").clicked() {
+                        picked = Some(entry.level);
+                    }
+                });
+            });
+        }
+        picked
+    }
+}
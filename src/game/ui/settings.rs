@@ -82,4 +82,8 @@ make_settings_struct!(pub Settings {
     zoom: "zoom", Slider, (1.0, 0.25..=4.0),
     camera_speed: "camera speed", Slider, (0.1, 0.01..=1.0),
     fullscreen: "fullscreen", Toggle, false,
+    speedrun_timer: "speedrun timer", Toggle, false,
+    ghost: "ghost", Toggle, true,
+    minimap: "minimap", Toggle, true,
+    debug_overlay: "debug overlay", Toggle, false,
 });
@@ -1,71 +1,225 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
+use crate::consts::{LEVEL_X, LEVEL_Y};
 use crate::game::{
+    SavedWorld,
     config::GameConfig,
-    world::{back::Back, level::LevelId},
+    world::{back::Back, level::LevelId, thing::ThingId},
 };
 
 use self::level::load_level;
 
-use hecs::World as HecsWorld;
+use hecs::{Entity, Ref, RefMut, World as HecsWorld};
 use macroquad::prelude::*;
+use rapier2d::prelude::RigidBodyHandle;
 
 pub mod physics_world;
+use camera::CameraController;
+use draw::light_map::LightMap;
+use events::Contacts;
+use ghost::Ghost;
 use physics_world::PhysicsWorld;
+use schedule::Schedule;
+use signal::Signals;
+use speedrun::Speedrun;
 pub mod floor;
 pub mod player;
-use player::Player;
+use life_state::LifeState;
+use player::{Body, Player, RespawnProgress};
+use snapshot::LevelSnapshot;
+use stream::LevelStreamer;
 
 use super::{assets::Assets, ui::settings::Settings};
 pub mod back;
+pub mod camera;
 pub mod collider;
 pub mod draw;
+pub mod events;
 pub mod frame;
+pub mod ghost;
 pub mod level;
+pub mod level_cache;
+pub mod level_graph;
 pub mod life_state;
 pub mod light;
+pub mod minimap;
+pub mod one_way;
+pub mod platform;
 pub mod polygon;
+pub mod prop;
+pub mod schedule;
+pub mod signal;
+pub mod snapshot;
+pub mod speedrun;
+pub mod stream;
 pub mod svg;
 pub mod thing;
 pub mod update;
+pub mod water;
+pub mod wind;
 
 pub struct World {
-    pub player: Player,
+    /// entity with the `Player`, `Body`, `LifeState` and `RespawnProgress` components
+    pub player: Entity,
     pub entities: HecsWorld,
     pub camera: Camera2D,
+    pub camera_controller: CameraController,
+    /// `None` for previews, which are drawn without lighting
+    pub light_map: Option<LightMap>,
     pub physics_world: PhysicsWorld,
+    pub contacts: Contacts,
     pub levels: HashMap<LevelId, Vec2>,
+    /// level the player is in, or was in last when between levels
+    pub current_level: LevelId,
+    /// where `current_level` was, kept after it unloads so falls out of it still end
+    pub current_bounds: Rect,
     pub back: Back,
+    pub signals: Signals,
+    /// collectibles picked up so far, which aren't spawned again
+    pub collected: HashSet<(LevelId, ThingId)>,
+    /// state of levels that got unloaded, put back when they load again
+    pub snapshots: HashMap<LevelId, LevelSnapshot>,
+    pub streamer: LevelStreamer,
+    pub speedrun: Speedrun,
+    pub ghost: Ghost,
+    /// levels the player has been in this game
+    pub reached: HashSet<LevelId>,
+    pub schedule: Schedule,
 }
 
 impl World {
-    pub fn new(_settings: &Settings, assets: &mut Assets, config: &GameConfig) -> Self {
+    pub fn new(settings: &Settings, assets: &mut Assets, config: &GameConfig) -> Self {
+        let start_level = LevelId(config.level.unwrap_or(0));
+        Self::at_level(settings, assets, config, start_level)
+    }
+    /// Starts a new game at the first respawn of `level`.
+    pub fn at_level(
+        _settings: &Settings,
+        assets: &mut Assets,
+        config: &GameConfig,
+        level: LevelId,
+    ) -> Self {
+        // runs that skip ahead or cheat don't count towards the records
+        let ranked = level == LevelId::first() && !config.cheat;
+        let light_map = assets.light_map.take().unwrap_or_default();
+        let mut world = Self::build(assets, level, ranked, Some(light_map), |_| {});
+        world.ghost.load_best(assets.levels.keys().copied());
+        world
+    }
+    /// Continues from a save, respawning at the saved respawn.
+    pub fn from_save(_settings: &Settings, assets: &mut Assets, saved: &SavedWorld) -> Self {
+        let (level, thing) = saved.respawn;
+        let light_map = assets.light_map.take().unwrap_or_default();
+        let mut world = Self::build(assets, LevelId(level), false, Some(light_map), |world| {
+            for &(level, thing) in saved.respawns.iter() {
+                world
+                    .respawns_mut()
+                    .set_respawn((LevelId(level), ThingId(thing)));
+            }
+            world
+                .respawns_mut()
+                .set_respawn((LevelId(level), ThingId(thing)));
+            world.signals = Signals(saved.signals.clone());
+            world.collected = saved
+                .collected
+                .iter()
+                .map(|&(level, thing)| (LevelId(level), ThingId(thing)))
+                .collect();
+        });
+        world.ghost.load_best(assets.levels.keys().copied());
+        world
+    }
+    /// World with only `level` loaded and nothing restored, for rendering the level on its own.
+    pub fn preview(assets: &Assets, level: LevelId) -> Self {
+        Self::build(assets, level, false, None, |_| {})
+    }
+    fn build(
+        assets: &Assets,
+        start_level: LevelId,
+        ranked: bool,
+        light_map: Option<LightMap>,
+        restore: impl FnOnce(&mut Self),
+    ) -> Self {
         println!("Loading world...");
-        let mut physics_world = PhysicsWorld::new();
+        let physics_world = PhysicsWorld::new();
         let camera = Camera2D {
             target: vec2(0.0, 3.0),
             ..Default::default()
         };
-        let start_level = LevelId(config.level.unwrap_or(0));
         let entities = HecsWorld::new();
 
-        let player = Player::spawn(&mut physics_world, start_level);
-
         let levels = HashMap::new();
 
         let back = Back::new(start_level);
 
         let mut world = Self {
-            player,
+            player: Entity::DANGLING,
             entities,
+            camera_controller: CameraController::new(camera.target),
+            light_map,
             camera,
             physics_world,
+            contacts: Contacts::default(),
             levels,
+            current_level: start_level,
+            current_bounds: Rect::new(
+                LEVEL_X,
+                LEVEL_Y,
+                assets.levels[&start_level.0].0.dims.x,
+                assets.levels[&start_level.0].0.dims.y,
+            ),
             back,
+            signals: Signals::default(),
+            collected: HashSet::new(),
+            snapshots: HashMap::new(),
+            streamer: LevelStreamer::new(assets),
+            speedrun: Speedrun::new(ranked),
+            ghost: Ghost::new(start_level),
+            reached: HashSet::from_iter([start_level]),
+            schedule: update::schedule(),
         };
+        world.player = Player::spawn(&mut world, start_level);
+        // restore before loading, so the level spawns with the saved state
+        restore(&mut world);
 
         load_level(assets, &mut world, start_level);
 
         world
     }
 }
+
+/// Components of the player entity, which always exist.
+impl World {
+    pub fn player(&self) -> Ref<'_, Player> {
+        self.entities.get::<&Player>(self.player).unwrap()
+    }
+    pub fn player_mut(&self) -> RefMut<'_, Player> {
+        self.entities.get::<&mut Player>(self.player).unwrap()
+    }
+    pub fn form(&self) -> Ref<'_, Body> {
+        self.entities.get::<&Body>(self.player).unwrap()
+    }
+    pub fn form_mut(&self) -> RefMut<'_, Body> {
+        self.entities.get::<&mut Body>(self.player).unwrap()
+    }
+    pub fn life_state(&self) -> Ref<'_, LifeState> {
+        self.entities.get::<&LifeState>(self.player).unwrap()
+    }
+    pub fn life_state_mut(&self) -> RefMut<'_, LifeState> {
+        self.entities.get::<&mut LifeState>(self.player).unwrap()
+    }
+    pub fn player_body_handle(&self) -> RigidBodyHandle {
+        *self.entities.get::<&RigidBodyHandle>(self.player).unwrap()
+    }
+    pub fn player_alive(&self) -> bool {
+        self.life_state().alive()
+    }
+    pub fn respawns(&self) -> Ref<'_, RespawnProgress> {
+        self.entities.get::<&RespawnProgress>(self.player).unwrap()
+    }
+    pub fn respawns_mut(&self) -> RefMut<'_, RespawnProgress> {
+        self.entities
+            .get::<&mut RespawnProgress>(self.player)
+            .unwrap()
+    }
+}
@@ -1,5 +1,6 @@
 use crate::{consts::BACKGROUND_TRANSITION_SPEED, game::world::level::LevelId};
 
+#[derive(Debug, Clone)]
 pub struct Back {
     pub fade: f32,
     pub previous: Option<LevelId>,
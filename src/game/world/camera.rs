@@ -0,0 +1,121 @@
+use macroquad::prelude::*;
+
+use crate::consts::*;
+
+use super::svg::{SvgItem, SvgShape};
+
+/// Area that changes how the camera follows the player while the player is inside.
+///
+/// Authored as an invisible rect with `data-camera` set to any of `lock-x`, `lock-y` (keep
+/// the camera on the zone's center along that axis) and `frame` (show the whole zone), and
+/// optionally `data-zoom` to scale the zoom.
+#[derive(Debug, Clone)]
+pub struct CameraZone {
+    pub area: Rect,
+    pub lock_x: bool,
+    pub lock_y: bool,
+    pub frame: bool,
+    pub zoom: Option<f32>,
+}
+
+impl CameraZone {
+    pub fn from_svg_item(item: &SvgItem, pos: Vec2) -> Option<Self> {
+        let flags = item.data("camera")?;
+        let SvgShape::Rect(rect) = &item.shape else {
+            panic!("camera zones must be rects");
+        };
+        let mut zone = Self {
+            area: Rect::new(
+                rect.pos.x + pos.x - rect.dims.x / 2.0,
+                rect.pos.y + pos.y - rect.dims.y / 2.0,
+                rect.dims.x,
+                rect.dims.y,
+            ),
+            lock_x: false,
+            lock_y: false,
+            frame: false,
+            zoom: item.data_f32("zoom"),
+        };
+        for flag in flags.split_whitespace() {
+            match flag {
+                "lock-x" => zone.lock_x = true,
+                "lock-y" => zone.lock_y = true,
+                "frame" => zone.frame = true,
+                _ => panic!("unknown camera zone flag: {}", flag),
+            }
+        }
+        Some(zone)
+    }
+}
+
+/// State of the camera between frames. The camera itself is rebuilt from this every frame,
+/// with shake added on top.
+#[derive(Debug, Clone)]
+pub struct CameraController {
+    /// smoothed camera target, without shake
+    pub pos: Vec2,
+    /// point followed with the dead-zone, lags behind the player vertically
+    pub focus: Vec2,
+    pub look_ahead: f32,
+    /// multiplier of the user's zoom, eased towards the zoom of the current zone
+    pub zoom: f32,
+    /// 0 is still, 1 is the strongest shake, decays over time
+    pub trauma: f32,
+    time: f32,
+}
+
+impl CameraController {
+    pub fn new(pos: Vec2) -> Self {
+        Self {
+            pos,
+            focus: pos,
+            look_ahead: 0.0,
+            zoom: 1.0,
+            trauma: 0.0,
+            time: 0.0,
+        }
+    }
+
+    /// Shakes the camera, `amount` between 0 and 1 adds up with shakes already going on.
+    pub fn shake(&mut self, amount: f32) {
+        self.trauma = (self.trauma + amount).min(1.0);
+    }
+
+    /// Shakes the camera for hits on the player, like landing hard.
+    pub fn impact(&mut self, impulse: f32) {
+        if impulse > CAMERA_IMPACT_IMPULSE {
+            self.shake((impulse - CAMERA_IMPACT_IMPULSE) * CAMERA_IMPACT_SHAKE);
+        }
+    }
+
+    /// Moves the focus with the player, only following vertically outside the dead-zone or
+    /// once the player is back on the ground.
+    pub fn follow(&mut self, player_pos: Vec2, linvel: Vec2, grounded: bool, dt: f32) {
+        self.focus.x = player_pos.x;
+        let dy = player_pos.y - self.focus.y;
+        if dy.abs() > CAMERA_DEAD_ZONE {
+            self.focus.y += dy - dy.signum() * CAMERA_DEAD_ZONE;
+        }
+        if grounded {
+            let dy = player_pos.y - self.focus.y;
+            self.focus.y += dy * (CAMERA_RECENTER_SPEED * dt).min(1.0);
+        }
+        let look_ahead =
+            (linvel.x * CAMERA_LOOK_AHEAD).clamp(-CAMERA_LOOK_AHEAD_MAX, CAMERA_LOOK_AHEAD_MAX);
+        self.look_ahead += (look_ahead - self.look_ahead) * (CAMERA_LOOK_AHEAD_SPEED * dt).min(1.0);
+    }
+
+    /// Offset and rotation (degrees) of the shake, decaying the trauma.
+    pub fn tick_shake(&mut self, dt: f32) -> (Vec2, f32) {
+        self.time += dt;
+        self.trauma = (self.trauma - CAMERA_SHAKE_DECAY * dt).max(0.0);
+        // squared, so small shakes stay subtle
+        let shake = self.trauma * self.trauma;
+        let t = self.time * CAMERA_SHAKE_FREQUENCY;
+        let noise = |seed: f32| ((t + seed).sin() + (t * 2.3 + seed * 1.7).sin() * 0.5) / 1.5;
+        (
+            vec2(noise(0.0), noise(10.0)) * shake * CAMERA_SHAKE_OFFSET,
+            noise(20.0) * shake * CAMERA_SHAKE_ROTATION,
+        )
+    }
+}
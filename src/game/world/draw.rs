@@ -3,21 +3,93 @@ use crate::{
     game::{Settings, assets::Assets, world::level::LevelId},
 };
 
-use super::{World, life_state::LifeState, light::LightGroup, polygon::three_points_rect};
+use super::{
+    World,
+    life_state::LifeState,
+    light::LightGroup,
+    minimap::nearby_levels,
+    polygon::three_points_rect,
+    signal::{Trigger, TriggerArea, TriggerKind},
+    speedrun::format_time,
+    wind::Wind,
+};
+use itertools::Itertools;
 use macroquad::prelude::*;
+use nalgebra::Isometry2;
 use rapier2d::dynamics::RigidBodyHandle;
 
 pub mod floor;
+pub mod light_map;
 pub mod player;
 
+/// What to leave out of a frame, for photo mode.
+#[derive(Debug, Clone)]
+pub struct DrawOptions {
+    pub hud: bool,
+    pub player: bool,
+}
+
+impl Default for DrawOptions {
+    fn default() -> Self {
+        Self {
+            hud: true,
+            player: true,
+        }
+    }
+}
+
 pub fn draw(settings: &Settings, assets: &Assets, world: &World) {
+    draw_with(settings, assets, world, &DrawOptions::default());
+}
+
+pub fn draw_with(settings: &Settings, assets: &Assets, world: &World, options: &DrawOptions) {
     set_camera(&world.camera);
     draw_back(settings, assets, world);
-    player::draw(assets, world);
+    if options.player {
+        if settings.ghost.value {
+            player::draw_ghost(assets, world);
+        }
+        player::draw(assets, world);
+    }
     floor::draw(assets, world);
+    draw_wind(world);
+    draw_levers(world);
+    draw_light_map(assets, world);
     draw_light(world);
 
+    if !options.hud {
+        return;
+    }
     draw_life_state(world);
+    draw_hud(assets, world);
+    if settings.speedrun_timer.value {
+        draw_speedrun(world);
+    }
+    if settings.minimap.value {
+        draw_minimap(assets, world);
+    }
+}
+
+/// Renders the floors and things of `level` on their own into a new render target, with
+/// `scale` render pixels per svg pixel. Large levels are scaled down to fit a texture.
+pub fn render_level(assets: &Assets, level: LevelId, scale: f32) -> RenderTarget {
+    let mut world = World::preview(assets, level);
+    let dims = assets.levels[&level.0].0.dims;
+    let size = meter_to_pixel(dims) * scale;
+    let size = size * (LEVEL_RENDER_MAX_SIZE / size.max_element()).min(1.0);
+    let target = render_target(size.x.ceil() as u32, size.y.ceil() as u32);
+    // culling goes through the world camera, so it has to cover the whole level
+    world.camera = Camera2D {
+        target: vec2(LEVEL_X, LEVEL_Y) + dims / 2.0,
+        zoom: vec2(2.0 / dims.x, -2.0 / dims.y),
+        render_target: Some(target),
+        ..Default::default()
+    };
+    set_camera(&world.camera);
+    clear_background(BLANK);
+    floor::draw(assets, &world);
+    set_default_camera();
+    target
 }
 
 pub fn draw_texture_centered(
@@ -59,12 +131,31 @@ pub fn draw_texture_centered_lazy(
     pos: Vec2,
     rotation: f32,
     params: Option<DrawTextureParams>,
+) -> bool {
+    draw_texture_centered_in_view(
+        get_camera_rect(world),
+        assets,
+        texture_file,
+        pos,
+        rotation,
+        params,
+    )
+}
+
+/// Like `draw_texture_centered_lazy`, but culled against `view` instead of the camera
+pub fn draw_texture_centered_in_view(
+    view: Rect,
+    assets: &Assets,
+    texture_file: &str,
+    pos: Vec2,
+    rotation: f32,
+    params: Option<DrawTextureParams>,
 ) -> bool {
     let (size, _) = &assets[texture_file];
     let size =
         Vec2::new(pixel_to_meter(size.0 as f32), pixel_to_meter(size.1 as f32)) * 2.0f32.sqrt();
     let rect = Rect::new(pos.x - size.x / 2.0, pos.y - size.y / 2.0, size.x, size.y);
-    if !get_camera_rect(world).overlaps(&rect) {
+    if !view.overlaps(&rect) {
         return false;
     }
     draw_texture_centered(assets, texture_file, pos, rotation, params);
@@ -124,14 +215,14 @@ fn draw_irregular_polygon(vertices: &[Vec2], color: Color) {
     }
 }
 
-fn draw_trimesh_lazy(world: &World, vertices: &[Vec2], indices: &[[u32; 3]], color: Color) {
+fn draw_trimesh_lazy(view: Rect, vertices: &[Vec2], indices: &[[u32; 3]], color: Color) {
     for [v1, v2, v3] in indices {
         let [v1, v2, v3] = [
             vertices[*v1 as usize],
             vertices[*v2 as usize],
             vertices[*v3 as usize],
         ];
-        if !get_camera_rect(world).overlaps(&three_points_rect(v1, v2, v3)) {
+        if !view.overlaps(&three_points_rect(v1, v2, v3)) {
             continue;
         }
         draw_triangle(v1, v2, v3, color);
@@ -143,8 +234,14 @@ fn draw_back(_settings: &Settings, assets: &Assets, world: &World) {
         ((Some("sky_up"), "sky", None), 0.2),
         ((None, "hills", Some("hills_down")), 0.4),
     ];
+    // levels without a background of their own share the first level's
     let level_texture = |level: LevelId, texture_postfix: &str| -> String {
-        format!("{}_{}", level.0, texture_postfix)
+        let texture = format!("{}_{}", level.0, texture_postfix);
+        if assets.texture_size(&texture).is_some() {
+            texture
+        } else {
+            format!("{}_{}", LevelId::first().0, texture_postfix)
+        }
     };
     let (previous, target_alpha, target) = world.back.render();
     let level_alphas = if target_alpha == 1.0 {
@@ -267,13 +364,43 @@ pub fn get_camera_rect(world: &World) -> Rect {
     // )
 }
 
+/// Draws everything until `pop_isometry` transformed by `isometry`.
+pub fn push_isometry(isometry: &Isometry2<f32>) {
+    let translation = isometry.translation.vector;
+    let matrix = Mat4::from_rotation_translation(
+        Quat::from_rotation_z(isometry.rotation.angle()),
+        vec3(translation.x, translation.y, 0.0),
+    );
+    let gl = unsafe { get_internal_gl() };
+    gl.quad_gl.push_model_matrix(matrix);
+}
+
+pub fn pop_isometry() {
+    let gl = unsafe { get_internal_gl() };
+    gl.quad_gl.pop_model_matrix();
+}
+
+/// Bounding rect of `rect` moved by the inverse of `isometry`.
+pub fn inverse_transform_rect(isometry: &Isometry2<f32>, rect: Rect) -> Rect {
+    let corners = [
+        rect.point(),
+        rect.point() + vec2(rect.w, 0.0),
+        rect.point() + vec2(0.0, rect.h),
+        rect.point() + rect.size(),
+    ]
+    .map(|corner| Vec2::from(isometry.inverse_transform_point(&corner.into())));
+    let start = corners.iter().fold(corners[0], |a, b| a.min(*b));
+    let end = corners.iter().fold(corners[0], |a, b| a.max(*b));
+    Rect::new(start.x, start.y, end.x - start.x, end.y - start.y)
+}
+
 pub fn pos_in_camera(world: &World, pos: Vec2) -> bool {
     get_camera_rect(world).contains(pos)
 }
 
 fn draw_life_state(world: &World) {
     set_default_camera();
-    let darkness = match &world.player.life_state {
+    let darkness = match &*world.life_state() {
         LifeState::Alive(t) => t.get(),
         LifeState::Dead(t) => 1.0 - t.get(),
     };
@@ -289,12 +416,285 @@ fn draw_life_state(world: &World) {
     );
 }
 
+fn draw_wind(world: &World) {
+    let view = get_camera_rect(world);
+    for (_, wind) in world.entities.query::<&Wind>().iter() {
+        if !view.overlaps(&wind.bounds) {
+            continue;
+        }
+        for particle in wind.particles.iter() {
+            let alpha = particle.alpha();
+            if particle.leaf {
+                let size = pixel_to_meter(vec2(14.0, 6.0));
+                let color = Color::from_hex(0x409F84);
+                draw_rectangle_ex(
+                    particle.pos.x,
+                    particle.pos.y,
+                    size.x,
+                    size.y,
+                    DrawRectangleParams {
+                        offset: vec2(0.5, 0.5),
+                        rotation: particle.spin,
+                        color: Color { a: alpha, ..color },
+                    },
+                );
+            } else {
+                // streaks stretch with the strength of the current
+                let tail = particle.pos - wind.dir * wind.strength * WIND_PARTICLE_SPEED * 0.1;
+                draw_line(
+                    tail.x,
+                    tail.y,
+                    particle.pos.x,
+                    particle.pos.y,
+                    pixel_to_meter(3.0),
+                    Color::new(1.0, 1.0, 1.0, alpha * 0.5),
+                );
+            }
+        }
+    }
+}
+
+/// Collectible counter for the level the player is in, drawn in screen space.
+fn draw_hud(assets: &Assets, world: &World) {
+    let level = world.back.target;
+    let total = assets.levels[&level.0].0.collectibles;
+    if total == 0 {
+        return;
+    }
+    let collected = world
+        .collected
+        .iter()
+        .filter(|(collected_level, _)| *collected_level == level)
+        .count();
+    set_default_camera();
+    let (size, texture) = &assets["berry"];
+    draw_texture(*texture, 20.0, 20.0, WHITE);
+    draw_text(
+        &format!("{} / {}", collected, total),
+        30.0 + size.0 as f32,
+        20.0 + size.1 as f32 * 0.7,
+        32.0,
+        WHITE,
+    );
+}
+
+/// Run time and the latest splits in the top right, with deltas to the best splits.
+fn draw_speedrun(world: &World) {
+    set_default_camera();
+    let speedrun = &world.speedrun;
+    let right = screen_width() - 20.0;
+    let draw_right = |text: &str, y: f32, size: f32, color: Color| {
+        let width = measure_text(text, None, size as u16, 1.0).width;
+        draw_text(text, right - width, y, size, color);
+    };
+    draw_right(&format_time(speedrun.time), 50.0, 48.0, WHITE);
+    let shown = speedrun.splits.iter().rev().take(SPEEDRUN_SHOWN_SPLITS);
+    for (i, split) in shown.enumerate() {
+        let y = 90.0 + i as f32 * 30.0;
+        let (delta, color) = match split.delta {
+            _ if split.gold => (split.delta, Color::from_hex(0xE8A93A)),
+            Some(delta) if delta < 0.0 => (Some(delta), Color::from_hex(0x8BB661)),
+            Some(delta) => (Some(delta), Color::from_hex(0xE0475B)),
+            None => (None, WHITE),
+        };
+        let delta = delta.map_or(String::new(), |delta| {
+            let sign = if delta < 0.0 { "" } else { "+" };
+            format!("{}{}  ", sign, format_time(delta))
+        });
+        draw_right(
+            &format!(
+                "{}level {}  {}",
+                delta,
+                split.level.0,
+                format_time(split.time)
+            ),
+            y,
+            24.0,
+            color,
+        );
+    }
+}
+
+/// Outlines of the nearby levels around the player, in the bottom right corner.
+fn draw_minimap(assets: &Assets, world: &World) {
+    let player_body = world
+        .physics_world
+        .get_body(world.player_body_handle())
+        .unwrap();
+    let player_pos = Vec2::from(player_body.position().translation);
+    let range = vec2(
+        MINIMAP_RANGE,
+        MINIMAP_RANGE * MINIMAP_HEIGHT / MINIMAP_WIDTH,
+    );
+    // the viewport clips everything outside the minimap
+    set_camera(&Camera2D {
+        target: player_pos,
+        zoom: vec2(2.0 / range.x, -2.0 / range.y),
+        viewport: Some((
+            (screen_width() - MINIMAP_WIDTH - 20.0) as i32,
+            20,
+            MINIMAP_WIDTH as i32,
+            MINIMAP_HEIGHT as i32,
+        )),
+        ..Default::default()
+    });
+    // one screen pixel in meters
+    let px = MINIMAP_RANGE / MINIMAP_WIDTH;
+    draw_rectangle(
+        player_pos.x - range.x / 2.0,
+        player_pos.y - range.y / 2.0,
+        range.x,
+        range.y,
+        Color::new(0.0, 0.0, 0.0, 0.5),
+    );
+
+    let levels = nearby_levels(assets, world);
+    for (level, &(pos, loaded)) in levels.iter() {
+        let info = &assets.levels[&level.0].0;
+        let alpha = if loaded { 0.9 } else { 0.4 };
+        for floor in info.outline.floors.iter() {
+            for (&v1, &v2) in floor.iter().circular_tuple_windows() {
+                let (v1, v2) = (pos + v1, pos + v2);
+                draw_line(
+                    v1.x,
+                    v1.y,
+                    v2.x,
+                    v2.y,
+                    px * 2.0,
+                    Color::new(1.0, 1.0, 1.0, alpha),
+                );
+            }
+        }
+        let end = pos + info.markers.end;
+        draw_circle(end.x, end.y, px * 5.0, Color::from_hex(0xE0475B));
+        for door in info.markers.doors.values() {
+            let door = pos + *door;
+            draw_circle(door.x, door.y, px * 4.0, Color::from_hex(DOOR_COLOR));
+        }
+    }
+    for respawn in world.respawns().all_respawns() {
+        let (level, thing) = respawn;
+        let Some((pos, _)) = levels.get(level) else {
+            continue;
+        };
+        let Some(thing_pos) = assets.levels[&level.0].0.outline.things.get(thing) else {
+            continue;
+        };
+        let respawn_pos = *pos + *thing_pos;
+        let color = if *respawn == world.respawns().respawn() {
+            Color::from_hex(0xE8A93A)
+        } else {
+            Color::from_hex(0xCCCFAA)
+        };
+        draw_circle(respawn_pos.x, respawn_pos.y, px * 4.0, color);
+    }
+    draw_circle(player_pos.x, player_pos.y, px * 5.0, WHITE);
+    set_default_camera();
+}
+
+fn draw_levers(world: &World) {
+    for (_, trigger) in world.entities.query::<&Trigger>().iter() {
+        let TriggerKind::Lever(area) = trigger.kind else {
+            continue;
+        };
+        let (base, length) = match area {
+            TriggerArea::Rect(rect) => (vec2(rect.x + rect.w / 2.0, rect.y + rect.h), rect.h),
+            TriggerArea::Circle(center, r) => (center + vec2(0.0, r), r * 2.0),
+        };
+        let angle = if trigger.active {
+            LEVER_ANGLE
+        } else {
+            -LEVER_ANGLE
+        };
+        let tip = base + Vec2::from_angle(angle).rotate(vec2(0.0, -length * 0.8));
+        draw_line(
+            base.x,
+            base.y,
+            tip.x,
+            tip.y,
+            pixel_to_meter(8.0),
+            Color::from_hex(0x495380),
+        );
+        draw_circle(
+            tip.x,
+            tip.y,
+            pixel_to_meter(12.0),
+            Color::from_hex(0xCCCFAA),
+        );
+    }
+}
+
+/// Darkens the scene by the ambient darkness of the level, except around lights and the
+/// player. Glows are drawn afterwards, so they stay bright.
+fn draw_light_map(assets: &Assets, world: &World) {
+    let (previous, fade, target) = world.back.render();
+    let darkness = |level: LevelId| assets.levels[&level.0].0.darkness;
+    let darkness = lerp(darkness(previous), darkness(target), fade);
+    if darkness <= 0.0 {
+        return;
+    }
+    let time = get_time() as f32;
+    let Some(light_map) = &world.light_map else {
+        return;
+    };
+    light_map.begin(&world.camera, 1.0 - darkness);
+    for (_, (body, light_group)) in world
+        .entities
+        .query::<(&RigidBodyHandle, &LightGroup)>()
+        .iter()
+    {
+        if !light_group.enabled {
+            continue;
+        }
+        let body = world.physics_world.get_body(*body).unwrap();
+        let pos = Vec2::from(body.position().translation.vector);
+        let angle = body.position().rotation.angle();
+        for (light, light_state) in &light_group.lights {
+            let pos = pos + light.pos.rotate(Vec2::from_angle(angle));
+            let radius = light.radius * LIGHT_MAP_RADIUS;
+            if !get_camera_rect(world).overlaps(&Rect::new(
+                pos.x - radius,
+                pos.y - radius,
+                radius * 2.0,
+                radius * 2.0,
+            )) {
+                continue;
+            }
+            let brightness = light
+                .brightness(light_state, time)
+                .max(LIGHT_MAP_IDLE_STRENGTH * light.intensity);
+            let color = Color::new(
+                light.color.r * brightness,
+                light.color.g * brightness,
+                light.color.b * brightness,
+                1.0,
+            );
+            light_map.light(pos, radius, color, light.falloff);
+        }
+    }
+    let player_body = world
+        .physics_world
+        .get_body(world.player_body_handle())
+        .unwrap();
+    light_map.light(
+        Vec2::from(player_body.position().translation),
+        PLAYER_LIGHT_RADIUS,
+        Color::from_hex(PLAYER_LIGHT_COLOR),
+        LIGHT_MAP_FALLOFF,
+    );
+    light_map.apply(&world.camera);
+}
+
 fn draw_light(world: &World) {
+    let time = get_time() as f32;
     for (_, (body, light)) in world
         .entities
         .query::<(&RigidBodyHandle, &LightGroup)>()
         .iter()
     {
+        if !light.enabled {
+            continue;
+        }
         let body = world.physics_world.get_body(*body).unwrap();
         let pos = Vec2::from(body.position().translation.vector);
         let angle = body.position().rotation.angle();
@@ -309,7 +709,11 @@ fn draw_light(world: &World) {
             if !get_camera_rect(world).overlaps(&rect) {
                 continue;
             }
-            let color = Color::new(1.0, 1.0, 1.0, light_state.strength());
+            let alpha = light.brightness(light_state, time).min(1.0);
+            let color = Color {
+                a: alpha,
+                ..light.color
+            };
             draw_circle(pos.x, pos.y, light.radius, color);
         }
     }
@@ -2,40 +2,61 @@ use std::f32::consts::PI;
 
 use super::super::floor::VertexDraw;
 use super::super::polygon::{shrink_polygon, trimesh_from_polygon};
-use super::{draw_texture_centered_lazy, draw_trimesh_lazy, get_camera_rect, pixel_to_meter};
+use super::{
+    draw_texture_centered_in_view, draw_texture_centered_lazy, draw_trimesh_lazy, get_camera_rect,
+    inverse_transform_rect, pixel_to_meter, pop_isometry, push_isometry,
+};
 use crate::consts::*;
-use crate::game::assets::{Assets, TileConstraints};
+use crate::game::assets::{Assets, TileConstraints, Tiles};
 use crate::game::world::World;
 use crate::game::world::level::DrawLayer;
+use crate::game::world::platform::RestPose;
 use crate::game::world::polygon::{
     add_rect_padding, get_rect_offset_under_polygon_edge, two_points_rect,
 };
 use crate::game::world::thing::{Flytrap, ThingDraw};
+use crate::game::world::water::{WaterSurface, subdivide_surface};
 use hecs::Or;
 use itertools::Itertools;
 use macroquad::prelude::*;
 use rapier2d::dynamics::RigidBodyHandle;
 
 pub fn draw(assets: &Assets, world: &World) {
-    for (_, (draw, body, _)) in world
+    for (_, (draw, water_surface, rest, body, _)) in world
         .entities
         .query::<(
             Or<&VertexDraw, (&ThingDraw, Option<&Flytrap>)>,
+            Option<&WaterSurface>,
+            Option<&RestPose>,
             &RigidBodyHandle,
             &DrawLayer,
         )>()
         .iter()
-        .sorted_by_key(|(_, (_, _, draw_layer))| *draw_layer)
+        .sorted_by_key(|(_, (_, _, _, _, draw_layer))| *draw_layer)
     {
         if let Or::Left(vertex_draw) | Or::Both(vertex_draw, _) = draw {
+            // vertices are absolute, so kinematic floors are drawn displaced from their rest pose
+            let displacement = rest.map(|rest| {
+                rest.displacement(world.physics_world.get_body(*body).unwrap().position())
+            });
+            let view = match displacement {
+                Some(displacement) => {
+                    push_isometry(&displacement);
+                    inverse_transform_rect(&displacement, get_camera_rect(world))
+                }
+                None => get_camera_rect(world),
+            };
             match vertex_draw {
                 VertexDraw::Tiled(tiled_draw) => {
-                    draw_tiled(assets, world, tiled_draw);
+                    draw_tiled(assets, view, tiled_draw);
                 }
                 VertexDraw::Liquid(liquid_draw) => {
-                    draw_liquid(world, liquid_draw);
+                    draw_liquid(view, liquid_draw, water_surface);
                 }
             }
+            if displacement.is_some() {
+                pop_isometry();
+            }
         }
         if let Or::Right((thing_draw, flytrap)) | Or::Both(_, (thing_draw, flytrap)) = draw {
             let drew = draw_thing(assets, world, thing_draw, body);
@@ -46,9 +67,9 @@ pub fn draw(assets: &Assets, world: &World) {
     }
 }
 
-pub fn draw_tiled(assets: &Assets, world: &World, tiled_draw: &TiledDraw) {
+pub fn draw_tiled(assets: &Assets, view: Rect, tiled_draw: &TiledDraw) {
     for ((vertices, indices), color) in tiled_draw.trimeshes.iter().zip(tiled_draw.colors.iter()) {
-        draw_trimesh_lazy(world, vertices, indices, *color)
+        draw_trimesh_lazy(view, vertices, indices, *color)
     }
     let vertices = &tiled_draw.trimeshes[0].0;
     for ((_, &v1, &v2, _), (rect, left_offset, textures)) in vertices
@@ -56,7 +77,7 @@ pub fn draw_tiled(assets: &Assets, world: &World, tiled_draw: &TiledDraw) {
         .circular_tuple_windows()
         .zip(tiled_draw.tile_textures.iter())
     {
-        if !get_camera_rect(world).overlaps(rect) {
+        if !view.overlaps(rect) {
             continue;
         }
         let rotation = Vec2::new(1.0, 0.0).angle_between(v1 - v2);
@@ -65,7 +86,7 @@ pub fn draw_tiled(assets: &Assets, world: &World, tiled_draw: &TiledDraw) {
             let down = Vec2::from_angle(rotation_down) * pixel_to_meter(TILE_DOWN);
             let pos = v1 + (v2 - v1).normalize() * dist + down;
 
-            draw_texture_centered_lazy(world, assets, texture_file, pos, rotation, None);
+            draw_texture_centered_in_view(view, assets, texture_file, pos, rotation, None);
         };
         for (j, texture_file) in textures.iter().rev().enumerate() {
             let dist = j as f32 * pixel_to_meter(TILE_WIDTH)
@@ -79,30 +100,42 @@ pub fn draw_tiled(assets: &Assets, world: &World, tiled_draw: &TiledDraw) {
 #[derive(Debug, Clone)]
 pub struct LiquidDraw {
     pub color: Color,
+    pub surface_color: Color,
     pub vertices: Vec<Vec2>,
     pub indices: Vec<[u32; 3]>,
+    /// indices of the vertices along the top edge, see `WaterSurface`
+    pub surface: Vec<usize>,
 }
 
 impl LiquidDraw {
-    pub fn new(vertices: &[Vec2], color: Color) -> Self {
-        let indices = trimesh_from_polygon(vertices);
+    pub fn new(vertices: &[Vec2], color: Color, surface_color: Color) -> Self {
+        let (vertices, surface) = subdivide_surface(vertices);
+        let indices = trimesh_from_polygon(&vertices);
         Self {
             color,
+            surface_color,
             indices,
-            vertices: vertices.to_vec(),
+            vertices,
+            surface,
+        }
+    }
+    pub fn translated(&self, offset: Vec2) -> Self {
+        Self {
+            vertices: self.vertices.iter().map(|v| *v + offset).collect(),
+            ..self.clone()
         }
     }
 }
 #[derive(Debug, Clone)]
 pub struct TiledDraw {
-    pub tile: &'static str,
+    pub tile: String,
     pub colors: [Color; 3],
     pub trimeshes: [(Vec<Vec2>, Vec<[u32; 3]>); 3],
     pub tile_textures: Vec<(Rect, f32, Vec<String>)>,
 }
 
 impl TiledDraw {
-    pub fn new(assets: &Assets, tile: &'static str, colors: [Color; 3], vertices: &[Vec2]) -> Self {
+    pub fn new(tiles: &Tiles, tile: &'static str, colors: [Color; 3], vertices: &[Vec2]) -> Self {
         let shrink_1 = shrink_polygon(vertices, pixel_to_meter(40.0));
         let shrink_2 = shrink_polygon(&shrink_1, pixel_to_meter(40.0));
         let map = |vertices: Vec<Vec2>| {
@@ -134,7 +167,7 @@ impl TiledDraw {
 
                 let full_left_offset = left_offset
                     + (distance_offset - count as f32 * pixel_to_meter(TILE_WIDTH)) / 2.0;
-                let tile_textures = generate_textures_from_tile(assets, tile, v1.x, count)
+                let tile_textures = generate_textures_from_tile(tiles, tile, v1.x, count)
                     .into_iter()
                     .map(|s| s.to_string())
                     .collect();
@@ -144,22 +177,66 @@ impl TiledDraw {
             })
             .collect();
         Self {
-            tile,
+            tile: tile.to_owned(),
             colors,
             trimeshes,
             tile_textures,
         }
     }
+    pub fn translated(&self, offset: Vec2) -> Self {
+        let trimeshes = self
+            .trimeshes
+            .clone()
+            .map(|(vertices, indices)| (vertices.iter().map(|v| *v + offset).collect(), indices));
+        let tile_textures = self
+            .tile_textures
+            .iter()
+            .map(|(rect, left_offset, textures)| {
+                (rect.offset(offset), *left_offset, textures.clone())
+            })
+            .collect();
+        Self {
+            tile: self.tile.clone(),
+            colors: self.colors,
+            trimeshes,
+            tile_textures,
+        }
+    }
+}
+
+/// The generator of `rand` with its own state, so levels can be prepared on another thread
+/// without reseeding the main one.
+struct TileRng(u64);
+
+impl TileRng {
+    fn new(seed: u64) -> Self {
+        let mut rng = Self(0);
+        rng.next();
+        rng.0 = rng.0.wrapping_add(seed);
+        rng.next();
+        rng
+    }
+    fn next(&mut self) -> u32 {
+        let old = self.0;
+        self.0 = old
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        let xorshifted = (((old >> 18) ^ old) >> 27) as u32;
+        xorshifted.rotate_right((old >> 59) as u32)
+    }
+    fn gen_range(&mut self, low: f32, high: f32) -> f32 {
+        low + (high - low) * (self.next() as f32 / u32::MAX as f32)
+    }
 }
 
 pub fn generate_textures_from_tile<'a>(
-    assets: &'a Assets,
+    tiles: &'a Tiles,
     tile: &str,
     seed: f32,
     count: usize,
 ) -> Vec<&'a str> {
-    let tile = &assets.tiles[tile];
-    rand::srand(seed.to_bits() as u64);
+    let tile = &tiles[tile];
+    let mut rng = TileRng::new(seed.to_bits() as u64);
     let mut last_constraints = TileConstraints::zero();
     let mut textures = Vec::new();
     for i in 0..count {
@@ -180,7 +257,7 @@ pub fn generate_textures_from_tile<'a>(
             .iter()
             .map(|(_, constraints)| constraints.weight as f32)
             .collect::<Vec<_>>();
-        let mut random_val = rand::gen_range(0.0, weights.iter().sum());
+        let mut random_val = rng.gen_range(0.0, weights.iter().sum());
         let index = 'outer: {
             for (i, &weight) in weights.iter().enumerate() {
                 random_val -= weight;
@@ -197,13 +274,34 @@ pub fn generate_textures_from_tile<'a>(
     textures
 }
 
-pub fn draw_liquid(world: &World, liquid_draw: &LiquidDraw) {
-    draw_trimesh_lazy(
-        world,
-        &liquid_draw.vertices,
-        &liquid_draw.indices,
-        liquid_draw.color,
-    );
+pub fn draw_liquid(view: Rect, liquid_draw: &LiquidDraw, surface: Option<&WaterSurface>) {
+    let Some(surface) = surface else {
+        draw_trimesh_lazy(
+            view,
+            &liquid_draw.vertices,
+            &liquid_draw.indices,
+            liquid_draw.color,
+        );
+        return;
+    };
+    let vertices = surface.displace(&liquid_draw.vertices);
+    draw_trimesh_lazy(view, &vertices, &liquid_draw.indices, liquid_draw.color);
+
+    let line_color = liquid_draw.surface_color;
+    for (v1, v2) in surface.points().tuple_windows() {
+        let rect = add_rect_padding(two_points_rect(v1, v2), WATER_TINT_DEPTH);
+        if !view.overlaps(&rect) {
+            continue;
+        }
+        // tint steeper parts of the wave more, as if they refract more light
+        let slope = ((v2.y - v1.y) / (v2.x - v1.x).abs().max(f32::EPSILON)).abs();
+        let alpha = (0.15 + slope * 2.0).min(0.4);
+        let depth = vec2(0.0, WATER_TINT_DEPTH);
+        let tint = Color::new(line_color.r, line_color.g, line_color.b, alpha);
+        draw_triangle(v1, v2, v2 + depth, tint);
+        draw_triangle(v1, v2 + depth, v1 + depth, tint);
+        draw_line(v1.x, v1.y, v2.x, v2.y, pixel_to_meter(6.0), line_color);
+    }
 }
 
 fn draw_thing(
@@ -0,0 +1,166 @@
+use macroquad::miniquad::{BlendFactor, BlendState, BlendValue, Equation, PipelineParams};
+use macroquad::prelude::*;
+
+use crate::consts::LIGHT_MAP_MAX_FALLOFF;
+
+const VERTEX: &str = r#"#version 100
+attribute vec3 position;
+attribute vec2 texcoord;
+attribute vec4 color0;
+
+varying lowp vec2 uv;
+varying lowp vec4 color;
+
+uniform mat4 Model;
+uniform mat4 Projection;
+
+void main() {
+    gl_Position = Projection * Model * vec4(position, 1);
+    color = color0 / 255.0;
+    uv = texcoord;
+}"#;
+
+const LIGHT_FRAGMENT: &str = r#"#version 100
+precision lowp float;
+
+varying lowp vec2 uv;
+varying lowp vec4 color;
+
+void main() {
+    // alpha carries the falloff exponent over LIGHT_MAP_MAX_FALLOFF, so it fits in 0..1
+    float light = pow(clamp(1.0 - length(uv * 2.0 - 1.0), 0.0, 1.0), color.a * 4.0);
+    gl_FragColor = vec4(color.rgb * light, 1.0);
+}"#;
+
+const MULTIPLY_FRAGMENT: &str = r#"#version 100
+precision lowp float;
+
+varying lowp vec2 uv;
+
+uniform sampler2D Texture;
+
+void main() {
+    gl_FragColor = texture2D(Texture, uv);
+}"#;
+
+/// Screen sized texture of how lit each pixel is, starting at the ambient light and adding
+/// up radial lights. The scene is multiplied by it once everything lit is drawn.
+pub struct LightMap {
+    target: RenderTarget,
+    size: (u32, u32),
+    /// lights are drawn as textured quads so the shader gets uvs
+    white: Texture2D,
+    light_material: Material,
+    multiply_material: Material,
+}
+
+impl Default for LightMap {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LightMap {
+    pub fn new() -> Self {
+        let material = |fragment, color_blend| {
+            load_material(
+                VERTEX,
+                fragment,
+                MaterialParams {
+                    pipeline_params: PipelineParams {
+                        color_blend: Some(color_blend),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                },
+            )
+            .unwrap()
+        };
+        Self {
+            target: render_target(1, 1),
+            size: (1, 1),
+            white: Texture2D::from_rgba8(1, 1, &[255; 4]),
+            light_material: material(
+                LIGHT_FRAGMENT,
+                BlendState::new(Equation::Add, BlendFactor::One, BlendFactor::One),
+            ),
+            multiply_material: material(
+                MULTIPLY_FRAGMENT,
+                BlendState::new(
+                    Equation::Add,
+                    BlendFactor::Value(BlendValue::DestinationColor),
+                    BlendFactor::Zero,
+                ),
+            ),
+        }
+    }
+
+    /// Recreates the texture if the screen changed size.
+    pub fn fit_screen(&mut self) {
+        let size = (screen_width() as u32, screen_height() as u32);
+        if size == self.size || size.0 == 0 || size.1 == 0 {
+            return;
+        }
+        self.target.texture.delete();
+        self.target = render_target(size.0, size.1);
+        self.size = size;
+    }
+
+    /// Starts drawing lights seen by `camera`, on top of `ambient` light.
+    pub fn begin(&self, camera: &Camera2D, ambient: f32) {
+        set_camera(&Camera2D {
+            render_target: Some(self.target),
+            ..*camera
+        });
+        clear_background(Color::new(ambient, ambient, ambient, 1.0));
+        gl_use_material(self.light_material);
+    }
+
+    /// Adds a light that fades out towards `radius`, faster with a higher `falloff`.
+    pub fn light(&self, pos: Vec2, radius: f32, color: Color, falloff: f32) {
+        draw_texture_ex(
+            self.white,
+            pos.x - radius,
+            pos.y - radius,
+            Color {
+                a: falloff / LIGHT_MAP_MAX_FALLOFF,
+                ..color
+            },
+            DrawTextureParams {
+                dest_size: Some(vec2(radius * 2.0, radius * 2.0)),
+                ..Default::default()
+            },
+        );
+    }
+
+    /// Multiplies everything drawn so far with `camera` by the light map.
+    pub fn apply(&self, camera: &Camera2D) {
+        gl_use_default_material();
+        // covers the screen, or the render target the camera draws to
+        let size = match camera.render_target {
+            Some(target) => vec2(target.texture.width(), target.texture.height()),
+            None => vec2(screen_width(), screen_height()),
+        };
+        set_camera(&Camera2D {
+            target: size / 2.0,
+            zoom: vec2(2.0 / size.x, -2.0 / size.y),
+            render_target: camera.render_target,
+            ..Default::default()
+        });
+        gl_use_material(self.multiply_material);
+        draw_texture_ex(
+            self.target.texture,
+            0.0,
+            0.0,
+            WHITE,
+            DrawTextureParams {
+                dest_size: Some(size),
+                // render targets are stored bottom row first
+                flip_y: true,
+                ..Default::default()
+            },
+        );
+        gl_use_default_material();
+        set_camera(camera);
+    }
+}
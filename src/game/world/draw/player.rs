@@ -1,40 +1,105 @@
 use std::f32::consts::PI;
 
-use super::super::player::{Body, Polly};
+use super::super::player::Body;
 use super::lerp;
-use super::{draw_texture_centered, pixel_to_meter};
+use super::{draw_texture_centered_with_color, pixel_to_meter};
+use crate::consts::GHOST_ALPHA;
 use crate::game::assets::Assets;
 use crate::game::world::frame::Transition;
 use crate::game::world::life_state::LifeState;
 use crate::game::world::World;
 use macroquad::prelude::*;
 
+/// Everything needed to draw the player, so recorded runs can be drawn the same way.
+#[derive(Debug, Clone, Copy)]
+pub struct PlayerPose {
+    pub pos: Vec2,
+    pub rotation: f32,
+    pub eye_x: f32,
+    pub form: PlayerForm,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum PlayerForm {
+    Rolly,
+    Polly {
+        feet_frame: f32,
+    },
+    /// transitioning between rolly and polly
+    Olly {
+        time: f32,
+    },
+}
+
 pub fn draw(assets: &Assets, world: &World) {
-    if let LifeState::Alive(Transition::Start | Transition::Between { .. }) =
-        world.player.life_state
-    {
+    if let Some(pose) = pose(world) {
+        draw_pose(assets, &pose, WHITE);
+    }
+}
+
+/// Translucent replay of the best run, at the same run time as the current run.
+pub fn draw_ghost(assets: &Assets, world: &World) {
+    let (Some(best), true) = (&world.ghost.best, world.speedrun.started) else {
         return;
+    };
+    if let Some(pose) = best.pose_at(world.speedrun.time as f32) {
+        draw_pose(assets, &pose, Color::new(1.0, 1.0, 1.0, GHOST_ALPHA));
+    }
+}
+
+pub fn pose(world: &World) -> Option<PlayerPose> {
+    if let LifeState::Alive(Transition::Start | Transition::Between { .. }) = *world.life_state() {
+        return None;
     }
     let player = world
         .physics_world
-        .get_body(world.player.body.any_body_handle())
+        .get_body(world.player_body_handle())
         .unwrap();
     let pos: Vec2 = (*player.translation()).into();
     let rotation = player.rotation().angle();
+    let eye_x = world.player().eye_x.get();
 
-    match world.player.rolly_polly_transition {
-        Transition::Between { time, .. } => {
-            draw_olly(assets, world, pos, rotation, time);
-        }
-        _ => match world.player.body {
-            Body::Rolly(_) => draw_rolly(assets, world, pos, rotation),
-            Body::Polly(ref polly) => draw_polly(assets, world, polly, pos, rotation),
+    let (rotation, form) = match world.player().rolly_polly_transition {
+        Transition::Between { time, .. } => (rotation, PlayerForm::Olly { time }),
+        _ => match *world.form() {
+            Body::Rolly(_) => (rotation, PlayerForm::Rolly),
+            Body::Polly(ref polly) => {
+                // lean into the walk while grounded
+                let rotation = if polly.feet_grounded[1] {
+                    rotation + (player.linvel().x * 0.03).clamp(-0.3, 0.3)
+                } else {
+                    rotation
+                };
+                let feet_frame = polly.feet_frame.get();
+                (rotation, PlayerForm::Polly { feet_frame })
+            }
         },
     };
+    Some(PlayerPose {
+        pos,
+        rotation,
+        eye_x,
+        form,
+    })
 }
 
-fn draw_olly(assets: &Assets, world: &World, pos: Vec2, rotation: f32, time: f32) {
-    let eye_x = world.player.eye_x.get();
+pub fn draw_pose(assets: &Assets, pose: &PlayerPose, color: Color) {
+    let PlayerPose {
+        pos,
+        rotation,
+        eye_x,
+        form,
+    } = *pose;
+    match form {
+        PlayerForm::Olly { time } => draw_olly(assets, pos, rotation, time, eye_x, color),
+        PlayerForm::Rolly => draw_rolly(assets, pos, rotation, eye_x, color),
+        PlayerForm::Polly { feet_frame } => {
+            draw_polly(assets, pos, rotation, eye_x, feet_frame, color)
+        }
+    }
+}
+
+fn draw_olly(assets: &Assets, pos: Vec2, rotation: f32, time: f32, eye_x: f32, color: Color) {
     let parts = &[
         ("olly-big-back", vec2(0.0, 0.0), vec2(0.0, -10.0), 0.0, 0.0),
         ("olly-back", vec2(0.0, 0.0), vec2(0.0, -20.0), 0.0, 0.0),
@@ -66,11 +131,12 @@ fn draw_olly(assets: &Assets, world: &World, pos: Vec2, rotation: f32, time: f32
         let offset = pixel_to_meter(offset);
         let rotate_offset = lerp(*rotate_offset_start, *rotate_offset_end, eased);
 
-        draw_texture_centered(
+        draw_texture_centered_with_color(
             assets,
             filename,
             pos + offset,
             rotation + rotate_offset,
+            color,
             Some(DrawTextureParams {
                 pivot: Some(pos),
                 ..Default::default()
@@ -79,28 +145,23 @@ fn draw_olly(assets: &Assets, world: &World, pos: Vec2, rotation: f32, time: f32
     }
 }
 
-fn draw_polly(assets: &Assets, world: &World, polly: &Polly, pos: Vec2, rotation: f32) {
-    draw_feet(assets, world, polly, pos, rotation);
-
-    let velocity = world
-        .physics_world
-        .get_body(world.player.body.any_body_handle())
-        .unwrap()
-        .linvel()
-        .x;
-
-    let rotation = if polly.feet_grounded[1] {
-        rotation + (velocity * 0.03).clamp(-0.3, 0.3)
-    } else {
-        rotation
-    };
+fn draw_polly(
+    assets: &Assets,
+    pos: Vec2,
+    rotation: f32,
+    eye_x: f32,
+    feet_frame: f32,
+    color: Color,
+) {
+    draw_feet(assets, pos, rotation, feet_frame, color);
 
-    draw_texture_centered(assets, "polly", pos, rotation, None);
-    draw_texture_centered(
+    draw_texture_centered_with_color(assets, "polly", pos, rotation, color, None);
+    draw_texture_centered_with_color(
         assets,
         "olly-eye",
-        pos + pixel_to_meter(vec2(50.0 * world.player.eye_x.get(), 15.0)),
+        pos + pixel_to_meter(vec2(50.0 * eye_x, 15.0)),
         rotation,
+        color,
         Some(DrawTextureParams {
             pivot: Some(pos),
             ..Default::default()
@@ -108,13 +169,14 @@ fn draw_polly(assets: &Assets, world: &World, polly: &Polly, pos: Vec2, rotation
     );
 }
 
-fn draw_rolly(assets: &Assets, world: &World, pos: Vec2, rotation: f32) {
-    draw_texture_centered(assets, "rolly", pos, rotation, None);
-    draw_texture_centered(
+fn draw_rolly(assets: &Assets, pos: Vec2, rotation: f32, eye_x: f32, color: Color) {
+    draw_texture_centered_with_color(assets, "rolly", pos, rotation, color, None);
+    draw_texture_centered_with_color(
         assets,
         "olly-eye",
-        pos + pixel_to_meter(vec2(15.0 * world.player.eye_x.get(), 35.0)),
+        pos + pixel_to_meter(vec2(15.0 * eye_x, 35.0)),
         rotation,
+        color,
         Some(DrawTextureParams {
             pivot: Some(pos),
             ..Default::default()
@@ -122,20 +184,20 @@ fn draw_rolly(assets: &Assets, world: &World, pos: Vec2, rotation: f32) {
     );
 }
 
-fn draw_feet(assets: &Assets, _world: &World, polly: &Polly, pos: Vec2, rotation: f32) {
+fn draw_feet(assets: &Assets, pos: Vec2, rotation: f32, frame: f32, color: Color) {
     let draw_foot = |offset: Vec2| {
-        draw_texture_centered(
+        draw_texture_centered_with_color(
             assets,
             "polly-foot",
             pos + offset,
             rotation,
+            color,
             Some(DrawTextureParams {
                 pivot: Some(pos),
                 ..Default::default()
             }),
         );
     };
-    let frame = polly.feet_frame.get();
     for i in 1..5 {
         let x = i as f32 * 20.0 - 60.0 + frame * 20.0;
         let offset = pixel_to_meter(vec2(x, 25.0));
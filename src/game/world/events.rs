@@ -0,0 +1,148 @@
+use std::collections::HashSet;
+
+use hecs::Entity;
+use rapier2d::prelude::*;
+
+use super::World;
+use super::level::LevelId;
+use super::physics_world::PhysicsWorld;
+use super::prop::Prop;
+
+/// Something that happened to the player during the last physics step.
+#[derive(Debug, Clone, Copy)]
+pub enum GameEvent {
+    /// a solid collider of the player started touching the body of `entity`
+    PlayerTouched { entity: Entity },
+    /// a collider of the player started overlapping a sensor of `entity`, or a sensor of the
+    /// player started overlapping `entity`
+    PlayerEntered { entity: Entity },
+    /// the player got hit, `impulse` is the contact force over the step in N·s
+    Impact {
+        entity: Option<Entity>,
+        impulse: f32,
+    },
+}
+
+/// Pairs of (player collider, other collider) currently touching or overlapping, kept up to
+/// date from rapier's collision events instead of asking the narrow phase every frame.
+#[derive(Debug, Default)]
+pub struct Contacts {
+    pairs: HashSet<(ColliderHandle, ColliderHandle)>,
+    /// (prop collider, sensor collider) pairs currently overlapping
+    prop_pairs: HashSet<(ColliderHandle, ColliderHandle)>,
+    /// what happened during the last step
+    pub events: Vec<GameEvent>,
+}
+
+impl Contacts {
+    /// Entities touched or overlapped by `player_collider`, or by any player collider if
+    /// `None`. Pairs whose colliders were removed since the last step are skipped.
+    pub fn touching<'a>(
+        &'a self,
+        physics_world: &'a PhysicsWorld,
+        player_collider: Option<ColliderHandle>,
+    ) -> impl Iterator<Item = Entity> + 'a {
+        self.pairs
+            .iter()
+            .filter(move |(player, _)| player_collider.is_none_or(|collider| collider == *player))
+            .filter(|(player, _)| physics_world.get_collider(*player).is_some())
+            .filter_map(|(_, other)| collider_entity(physics_world, *other))
+    }
+
+    /// Entities whose sensors overlap `prop_collider`.
+    pub fn prop_overlapping<'a>(
+        &'a self,
+        physics_world: &'a PhysicsWorld,
+        prop_collider: ColliderHandle,
+    ) -> impl Iterator<Item = Entity> + 'a {
+        self.prop_pairs
+            .iter()
+            .filter(move |(prop, _)| *prop == prop_collider)
+            .filter_map(|(_, other)| collider_entity(physics_world, *other))
+    }
+}
+
+/// Links every body of `level` to its entity, so collisions can be traced back to it.
+pub fn link_bodies(world: &mut World, level: LevelId) {
+    for (entity, (handle, entity_level)) in
+        world.entities.query_mut::<(&RigidBodyHandle, &LevelId)>()
+    {
+        if *entity_level == level {
+            world.physics_world.get_body_mut(*handle).unwrap().user_data =
+                entity.to_bits().get().into();
+        }
+    }
+}
+
+/// Entity owning the body the collider is attached to, if it still exists.
+pub fn collider_entity(physics_world: &PhysicsWorld, collider: ColliderHandle) -> Option<Entity> {
+    let body = physics_world.get_collider(collider)?.parent()?;
+    Entity::from_bits(physics_world.get_body(body)?.user_data as u64)
+}
+
+/// Drains the events of the last physics step into `world.contacts`.
+pub fn update_events(world: &mut World) {
+    let player_body = world.player_body_handle();
+    let physics_world = &world.physics_world;
+    let is_player = |collider: ColliderHandle| {
+        physics_world
+            .get_collider(collider)
+            .and_then(Collider::parent)
+            .is_some_and(|body| body == player_body)
+    };
+    let entities = &world.entities;
+    let is_prop = |collider: ColliderHandle| {
+        collider_entity(physics_world, collider)
+            .is_some_and(|entity| entities.satisfies::<&Prop>(entity).unwrap_or(false))
+    };
+    let contacts = &mut world.contacts;
+    contacts.events.clear();
+
+    while let Ok(event) = physics_world.collision_events.try_recv() {
+        let (a, b) = (event.collider1(), event.collider2());
+        if event.stopped() {
+            // removed colliders can't be looked up anymore, so drop the pair either way round
+            contacts.pairs.remove(&(a, b));
+            contacts.pairs.remove(&(b, a));
+            contacts.prop_pairs.remove(&(a, b));
+            contacts.prop_pairs.remove(&(b, a));
+            continue;
+        }
+        let (player, other) = if is_player(a) {
+            (a, b)
+        } else if is_player(b) {
+            (b, a)
+        } else {
+            // props only care about the sensors they are in, like water
+            if event.sensor() {
+                if is_prop(a) {
+                    contacts.prop_pairs.insert((a, b));
+                } else if is_prop(b) {
+                    contacts.prop_pairs.insert((b, a));
+                }
+            }
+            continue;
+        };
+        contacts.pairs.insert((player, other));
+        if let Some(entity) = collider_entity(physics_world, other) {
+            contacts.events.push(if event.sensor() {
+                GameEvent::PlayerEntered { entity }
+            } else {
+                GameEvent::PlayerTouched { entity }
+            });
+        }
+    }
+
+    let dt = physics_world.integration_parameters.dt;
+    while let Ok(event) = physics_world.contact_force_events.try_recv() {
+        let other = if is_player(event.collider1) {
+            event.collider2
+        } else {
+            event.collider1
+        };
+        contacts.events.push(GameEvent::Impact {
+            entity: collider_entity(physics_world, other),
+            impulse: event.total_force_magnitude * dt,
+        });
+    }
+}
@@ -5,9 +5,11 @@ use super::level::LevelId;
 use super::polygon::{
     add_rect_padding, get_rect_offset_under_polygon_edge, trimesh_from_polygon, two_points_rect,
 };
+use super::water::WaterSurface;
 use crate::consts::*;
-use crate::game::assets::Assets;
+use crate::game::assets::Tiles;
 use crate::game::world::level::DrawLayer;
+use hecs::Entity;
 use itertools::Itertools;
 use macroquad::prelude::*;
 use rapier2d::prelude::*;
@@ -19,6 +21,11 @@ pub enum Material {
     Water,
     Mud,
     Fern,
+    Ice,
+    Honey,
+    Rubber,
+    /// thin fern leaves, which can be jumped up through
+    Leaf,
 }
 
 fn with_alpha(color: Color, alpha: f32) -> Color {
@@ -28,25 +35,72 @@ fn with_alpha(color: Color, alpha: f32) -> Color {
 impl Material {
     pub fn rigid(self) -> bool {
         match self {
-            Self::Grass | Self::Stone | Self::Mud | Self::Fern => true,
+            Self::Grass
+            | Self::Stone
+            | Self::Mud
+            | Self::Fern
+            | Self::Ice
+            | Self::Honey
+            | Self::Rubber
+            | Self::Leaf => true,
             Self::Water => false,
         }
     }
+    /// floors of it can be jumped up through from below
+    pub fn one_way(self) -> bool {
+        matches!(self, Self::Leaf)
+    }
+    /// multiplies the friction between the player and this material
+    pub fn grip(self) -> f32 {
+        match self {
+            Self::Ice => 0.05,
+            Self::Honey => 3.0,
+            _ => 1.0,
+        }
+    }
+    pub fn restitution(self) -> f32 {
+        match self {
+            Self::Rubber => 0.9,
+            _ => 0.0,
+        }
+    }
+    /// extra linear damping while the player touches this material
+    pub fn drag(self) -> f32 {
+        match self {
+            Self::Honey => 6.0,
+            _ => 0.0,
+        }
+    }
+    /// how much of polly's movement works while standing on this material
+    pub fn control(self) -> f32 {
+        match self {
+            Self::Ice => 0.25,
+            Self::Honey => 0.6,
+            _ => 1.0,
+        }
+    }
     pub fn from_hex_color(hex_color: u32) -> Self {
-        match hex_color {
+        Self::try_from_hex_color(hex_color)
+            .unwrap_or_else(|| panic!("unknown floor color: {:x}", hex_color))
+    }
+    pub fn try_from_hex_color(hex_color: u32) -> Option<Self> {
+        Some(match hex_color {
             0x50AA59 => Self::Grass,
             0x1E7EB4 => Self::Water,
             0x495380 => Self::Stone,
             0x63403D => Self::Mud,
             0x49A16F => Self::Fern,
-
-            _ => panic!("unknown floor color: {:x}", hex_color),
-        }
+            0xA9D6E5 => Self::Ice,
+            0xD18A28 => Self::Honey,
+            0xC24F76 => Self::Rubber,
+            0x8CC084 => Self::Leaf,
+            _ => return None,
+        })
     }
-    pub fn to_vertex_draw(self, assets: &Assets, vertices: &[Vec2]) -> VertexDraw {
+    pub fn to_vertex_draw(self, tiles: &Tiles, vertices: &[Vec2]) -> VertexDraw {
         match self {
             Self::Grass => VertexDraw::Tiled(TiledDraw::new(
-                assets,
+                tiles,
                 "grass",
                 [
                     Color::from_hex(0x8BB661),
@@ -56,7 +110,7 @@ impl Material {
                 vertices,
             )),
             Self::Stone => VertexDraw::Tiled(TiledDraw::new(
-                assets,
+                tiles,
                 "stone",
                 [
                     Color::from_hex(0x667696),
@@ -66,7 +120,7 @@ impl Material {
                 vertices,
             )),
             Self::Mud => VertexDraw::Tiled(TiledDraw::new(
-                assets,
+                tiles,
                 "mud",
                 [
                     Color::from_hex(0x775444),
@@ -76,7 +130,7 @@ impl Material {
                 vertices,
             )),
             Self::Fern => VertexDraw::Tiled(TiledDraw::new(
-                assets,
+                tiles,
                 "fern",
                 [
                     Color::from_hex(0x6BB97C),
@@ -85,9 +139,50 @@ impl Material {
                 ],
                 vertices,
             )),
+            Self::Ice => VertexDraw::Tiled(TiledDraw::new(
+                tiles,
+                "ice",
+                [
+                    Color::from_hex(0xCFEAF2),
+                    Color::from_hex(0xA9D6E5),
+                    Color::from_hex(0x86BFD6),
+                ],
+                vertices,
+            )),
+            Self::Honey => VertexDraw::Tiled(TiledDraw::new(
+                tiles,
+                "honey",
+                [
+                    Color::from_hex(0xE8A93A),
+                    Color::from_hex(0xD18A28),
+                    Color::from_hex(0xB36E1F),
+                ],
+                vertices,
+            )),
+            Self::Rubber => VertexDraw::Tiled(TiledDraw::new(
+                tiles,
+                "rubber",
+                [
+                    Color::from_hex(0xD96A8C),
+                    Color::from_hex(0xC24F76),
+                    Color::from_hex(0xA33D63),
+                ],
+                vertices,
+            )),
+            Self::Leaf => VertexDraw::Tiled(TiledDraw::new(
+                tiles,
+                "fern",
+                [
+                    Color::from_hex(0xA4D29A),
+                    Color::from_hex(0x8CC084),
+                    Color::from_hex(0x6BB97C),
+                ],
+                vertices,
+            )),
             Self::Water => VertexDraw::Liquid(LiquidDraw::new(
                 vertices,
                 with_alpha(Color::from_hex(0x1667B1), 0.7),
+                with_alpha(Color::from_hex(0x7FC4E8), 0.9),
             )),
         }
     }
@@ -99,59 +194,130 @@ pub enum VertexDraw {
     Liquid(LiquidDraw),
 }
 
+impl VertexDraw {
+    pub fn translated(&self, offset: Vec2) -> Self {
+        match self {
+            Self::Tiled(tiled_draw) => Self::Tiled(tiled_draw.translated(offset)),
+            Self::Liquid(liquid_draw) => Self::Liquid(liquid_draw.translated(offset)),
+        }
+    }
+}
+
+/// Drawing and colliders of a floor relative to its level, which take a while to make but
+/// don't need the world, so levels can prepare them ahead of time.
+#[derive(Debug, Clone)]
+pub struct FloorShape {
+    pub material: Material,
+    pub vertex_draw: VertexDraw,
+    pub colliders: FloorColliders,
+}
+
+#[derive(Debug, Clone)]
+pub enum FloorColliders {
+    /// built when the player gets close, one along each edge
+    Lazy(Vec<FloorEdge>),
+    /// trimesh of the whole polygon, for liquids
+    Sensor(Vec<Vec2>, Vec<[u32; 3]>),
+}
+
+/// Thin box just under one edge of a floor.
+#[derive(Debug, Clone, Copy)]
+pub struct FloorEdge {
+    /// where the player has to be for the collider to exist
+    pub rect: Rect,
+    pub pos: Vec2,
+    pub rotation: f32,
+    pub half_extents: Vec2,
+}
+
+impl FloorShape {
+    pub fn new(tiles: &Tiles, vertices: &[Vec2], material: Material) -> Self {
+        let vertex_draw = material.to_vertex_draw(tiles, vertices);
+        let colliders = if material.rigid() {
+            FloorColliders::Lazy(floor_edges(vertices))
+        } else {
+            FloorColliders::Sensor(vertices.to_vec(), trimesh_from_polygon(vertices))
+        };
+        Self {
+            material,
+            vertex_draw,
+            colliders,
+        }
+    }
+}
+
+/// Spawns `shape` into the level at `pos`.
 pub fn spawn_floor(
-    assets: &Assets,
     world: &mut World,
-    vertices: Vec<Vec2>,
-    material: Material,
-    level: LevelId,
+    shape: &FloorShape,
     pos: Vec2,
+    level: LevelId,
     draw_layer: DrawLayer,
-) {
-    let vertices = vertices.iter().map(|v| *v + pos).collect::<Vec<_>>();
-
-    let vertex_draw = material.to_vertex_draw(assets, &vertices);
-
+) -> Entity {
+    let material = shape.material;
     let body_handle = world.physics_world.add_body(
         RigidBodyBuilder::fixed()
             .translation(Vec2::ZERO.into())
             .build(),
     );
-    world
+    let vertex_draw = shape.vertex_draw.translated(pos);
+    let water_surface = match &vertex_draw {
+        VertexDraw::Liquid(liquid_draw) => Some(WaterSurface::new(liquid_draw)),
+        VertexDraw::Tiled(_) => None,
+    };
+    let entity = world
         .entities
         .spawn((body_handle, vertex_draw, material, level, draw_layer));
+    if let Some(water_surface) = water_surface {
+        world.entities.insert_one(entity, water_surface).unwrap();
+    }
 
-    if material.rigid() {
-        for (rect, builder) in polygon_colliders_from_rects(&vertices) {
-            let builder = builder
-                .friction(PLATFORM_FRICTION)
-                .friction_combine_rule(CoefficientCombineRule::Max);
-            world.entities.spawn((
-                LazyCollider {
-                    rect,
-                    builder,
-                    body_handle,
-                },
-                material,
-                level,
-            ));
+    match &shape.colliders {
+        FloorColliders::Lazy(edges) => {
+            for edge in edges {
+                let builder = ColliderBuilder::cuboid(edge.half_extents.x, edge.half_extents.y)
+                    .translation((edge.pos + pos).into())
+                    .rotation(edge.rotation)
+                    .collision_groups(InteractionGroups::new(
+                        COLLISION_LAYER_ENVIRONMENT.into(),
+                        (COLLISION_LAYER_PLAYER | COLLISION_LAYER_PROP).into(),
+                    ))
+                    .friction(PLATFORM_FRICTION * material.grip())
+                    .friction_combine_rule(CoefficientCombineRule::Max)
+                    .restitution(material.restitution())
+                    .restitution_combine_rule(CoefficientCombineRule::Max);
+                world.entities.spawn((
+                    LazyCollider {
+                        rect: edge.rect.offset(pos),
+                        builder,
+                        body_handle,
+                        rest: Isometry::identity(),
+                    },
+                    material,
+                    level,
+                ));
+            }
+        }
+        FloorColliders::Sensor(vertices, indices) => {
+            let vertices = vertices
+                .iter()
+                .map(|&v| (v + pos).into())
+                .collect::<Vec<_>>();
+            let builder = ColliderBuilder::trimesh(vertices, indices.clone()).sensor(true);
+            let handle = world.physics_world.add_collider(
+                builder
+                    .friction(PLATFORM_FRICTION)
+                    .friction_combine_rule(CoefficientCombineRule::Max)
+                    .build(),
+                body_handle,
+            );
+            world.entities.spawn((handle, material, level));
         }
-    } else {
-        let indices = trimesh_from_polygon(&vertices);
-        let vertices = vertices.iter().map(|&v| v.into()).collect::<Vec<_>>();
-        let builder = ColliderBuilder::trimesh(vertices, indices).sensor(true);
-        let handle = world.physics_world.add_collider(
-            builder
-                .friction(PLATFORM_FRICTION)
-                .friction_combine_rule(CoefficientCombineRule::Max)
-                .build(),
-            body_handle,
-        );
-        world.entities.spawn((handle, material, level));
     }
+    entity
 }
 
-fn polygon_colliders_from_rects(vertices: &[Vec2]) -> Vec<(Rect, ColliderBuilder)> {
+fn floor_edges(vertices: &[Vec2]) -> Vec<FloorEdge> {
     vertices
         .iter()
         .circular_tuple_windows()
@@ -166,22 +332,21 @@ fn polygon_colliders_from_rects(vertices: &[Vec2]) -> Vec<(Rect, ColliderBuilder
             let pos = v1
                 + (v2 - v1).normalize() * (left_offset + distance_offset / 2.0)
                 + Vec2::from_angle(rotation_down) * height / 2.0;
-            let builder = ColliderBuilder::cuboid(distance_offset / 2.0, height / 2.0)
-                .translation(pos.into())
-                .collision_groups(InteractionGroups::new(
-                    COLLISION_LAYER_ENVIRONMENT.into(),
-                    COLLISION_LAYER_PLAYER.into(),
-                ))
-                .rotation(rotation);
-            let rect = add_rect_padding(two_points_rect(v1, v2), pixel_to_meter(30.0));
-            (rect, builder)
+            FloorEdge {
+                rect: add_rect_padding(two_points_rect(v1, v2), pixel_to_meter(30.0)),
+                pos,
+                rotation,
+                half_extents: vec2(distance_offset / 2.0, height / 2.0),
+            }
         })
         .collect()
 }
 
 pub struct LazyCollider {
-    /// Rect is positioned absolutely
+    /// Rect is positioned absolutely, for the body at `rest`
     pub rect: Rect,
     pub builder: ColliderBuilder,
     pub body_handle: RigidBodyHandle,
+    /// pose of the body when the rect was computed, so moving bodies can be checked
+    pub rest: Isometry<Real>,
 }
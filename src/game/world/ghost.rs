@@ -0,0 +1,194 @@
+use macroquad::prelude::*;
+use nanoserde::{DeJson, SerJson};
+use ordered_float::OrderedFloat;
+
+use crate::consts::{GHOST_FRAME_INTERVAL, GHOST_MAX_FRAMES};
+
+use super::draw::player::{PlayerForm, PlayerPose};
+use super::level::LevelId;
+
+/// Player pose at a point in run time, flattened so it can be saved.
+#[derive(Debug, Clone, SerJson, DeJson)]
+pub struct GhostFrame {
+    pub time: f32,
+    pub visible: bool,
+    pub x: f32,
+    pub y: f32,
+    pub rotation: f32,
+    pub eye_x: f32,
+    /// 0 is rolly, 1 is polly, 2 is the transition between them
+    pub form: u8,
+    /// feet frame for polly, transition time for olly
+    pub frame: f32,
+}
+
+impl GhostFrame {
+    fn new(time: f32, pose: Option<PlayerPose>) -> Self {
+        let Some(pose) = pose else {
+            return Self {
+                time,
+                visible: false,
+                x: 0.0,
+                y: 0.0,
+                rotation: 0.0,
+                eye_x: 0.0,
+                form: 0,
+                frame: 0.0,
+            };
+        };
+        let (form, frame) = match pose.form {
+            PlayerForm::Rolly => (0, 0.0),
+            PlayerForm::Polly { feet_frame } => (1, feet_frame),
+            PlayerForm::Olly { time } => (2, time),
+        };
+        Self {
+            time,
+            visible: true,
+            x: pose.pos.x,
+            y: pose.pos.y,
+            rotation: pose.rotation,
+            eye_x: pose.eye_x,
+            form,
+            frame,
+        }
+    }
+
+    fn pose(&self) -> PlayerPose {
+        PlayerPose {
+            pos: vec2(self.x, self.y),
+            rotation: self.rotation,
+            eye_x: self.eye_x,
+            form: match self.form {
+                0 => PlayerForm::Rolly,
+                1 => PlayerForm::Polly {
+                    feet_frame: self.frame,
+                },
+                _ => PlayerForm::Olly { time: self.frame },
+            },
+        }
+    }
+}
+
+/// Recording of a run from level `start` until it entered level `end`.
+#[derive(Debug, Clone, SerJson, DeJson)]
+pub struct GhostRun {
+    pub start: usize,
+    pub end: usize,
+    pub time: f64,
+    pub frames: Vec<GhostFrame>,
+}
+
+impl GhostRun {
+    fn key(start: usize, end: usize) -> String {
+        format!("ghost-{}-{}", start, end)
+    }
+    pub fn load(start: usize, end: usize) -> Option<Self> {
+        let json = quad_storage::STORAGE
+            .lock()
+            .unwrap()
+            .get(&Self::key(start, end))?;
+        GhostRun::deserialize_json(&json).ok()
+    }
+    pub fn store(&self) {
+        quad_storage::STORAGE
+            .lock()
+            .unwrap()
+            .set(&Self::key(self.start, self.end), &self.serialize_json());
+    }
+
+    /// Pose at run time `time`, interpolated between the recorded frames.
+    pub fn pose_at(&self, time: f32) -> Option<PlayerPose> {
+        let index = self.frames.partition_point(|frame| frame.time < time);
+        let next = self.frames.get(index)?;
+        if !next.visible {
+            return None;
+        }
+        let mut pose = next.pose();
+        let prev = index
+            .checked_sub(1)
+            .map(|index| &self.frames[index])
+            .filter(|prev| prev.visible && prev.form == next.form);
+        if let Some(prev) = prev {
+            let t = (time - prev.time) / (next.time - prev.time).max(f32::EPSILON);
+            pose.pos = vec2(prev.x, prev.y).lerp(pose.pos, t);
+            pose.rotation = prev.rotation + (next.rotation - prev.rotation) * t;
+        }
+        Some(pose)
+    }
+}
+
+/// Records the current run, and plays back the furthest best run from the same start.
+pub struct Ghost {
+    pub start: LevelId,
+    pub recording: Vec<GhostFrame>,
+    pub best: Option<GhostRun>,
+    splits: usize,
+    /// run time between recorded frames, doubled whenever the recording fills up
+    interval: f32,
+}
+
+impl Ghost {
+    pub fn new(start: LevelId) -> Self {
+        Self {
+            start,
+            recording: Vec::new(),
+            best: None,
+            splits: 0,
+            interval: GHOST_FRAME_INTERVAL,
+        }
+    }
+
+    /// Loads the stored run into `levels` that took longest, which got furthest whatever
+    /// order the levels are numbered in.
+    pub fn load_best(&mut self, levels: impl Iterator<Item = usize>) {
+        let start = self.start.0;
+        self.best = levels
+            .filter(|end| *end != start)
+            .filter_map(|end| GhostRun::load(start, end))
+            .max_by_key(|run| OrderedFloat(run.time));
+    }
+
+    pub fn record(&mut self, time: f64, pose: Option<PlayerPose>) {
+        let time = time as f32;
+        if self
+            .recording
+            .last()
+            .is_some_and(|frame| time - frame.time < self.interval)
+        {
+            return;
+        }
+        // halve the frame rate instead of growing forever on long runs
+        if self.recording.len() >= GHOST_MAX_FRAMES {
+            let mut index = 0;
+            self.recording.retain(|_| {
+                index += 1;
+                index % 2 == 1
+            });
+            self.interval *= 2.0;
+        }
+        self.recording.push(GhostFrame::new(time, pose));
+    }
+
+    /// Stores the recording up to `level` if the run is ranked and beats the stored run over
+    /// the same range, so a new best survives the game closing before the world is saved.
+    pub fn split(&mut self, level: LevelId, time: f64, ranked: bool) {
+        self.splits += 1;
+        if !ranked {
+            return;
+        }
+        let beaten = GhostRun::load(self.start.0, level.0).is_none_or(|run| time < run.time);
+        if beaten {
+            GhostRun {
+                start: self.start.0,
+                end: level.0,
+                time,
+                frames: self.recording.clone(),
+            }
+            .store();
+        }
+    }
+
+    pub fn splits(&self) -> usize {
+        self.splits
+    }
+}
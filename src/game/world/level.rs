@@ -1,36 +1,32 @@
+use std::collections::HashMap;
+
 use macroquad::prelude::*;
 use rapier2d::dynamics::RigidBodyHandle;
 
 use crate::consts::*;
 use crate::game::assets::Assets;
 use crate::game::world::floor::Material;
-use crate::game::world::svg::{SvgShape, read_svg};
+use crate::game::world::svg::{SvgShape, read_svg, read_svg_data};
 use crate::game::world::thing::ThingInfo;
 
 use super::draw::{get_camera_rect, meter_to_pixel, pos_in_camera};
 use super::floor::spawn_floor;
 
 use super::World;
-use super::thing::{ThingId, spawn_thing};
+use super::camera::CameraZone;
+use super::events::link_bodies;
+use super::minimap::LevelOutline;
+use super::one_way::make_one_way;
+use super::platform::{Track, attach_platform, centroid};
+use super::signal::{Trigger, attach_receiver};
+use super::snapshot::{restore_snapshot, take_snapshot};
+use super::thing::{ThingId, ThingPart, spawn_thing};
+use super::wind::Wind;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct LevelId(pub usize);
 
 impl LevelId {
-    fn valid(&self, assets: &Assets) -> bool {
-        assets.levels.contains_key(&self.0)
-    }
-    fn next(&self, assets: &Assets) -> Option<LevelId> {
-        let next = LevelId(self.0 + 1);
-        if next.valid(assets) { Some(next) } else { None }
-    }
-    fn prev(&self, assets: &Assets) -> Option<LevelId> {
-        if self.0 == 0 {
-            return None;
-        }
-        let prev = LevelId(self.0 - 1);
-        if prev.valid(assets) { Some(prev) } else { None }
-    }
     pub fn first() -> LevelId {
         LevelId(0)
     }
@@ -40,12 +36,29 @@ impl LevelId {
 pub struct LevelInfo {
     pub dims: Vec2,
     pub markers: Markers,
+    /// number of collectibles placed in the level
+    pub collectibles: usize,
+    pub outline: LevelOutline,
+    /// ambient darkness from `data-darkness` on the svg tag, 0 is fully lit
+    pub darkness: f32,
 }
 
 #[derive(Debug, Clone)]
 pub struct Markers {
     pub start: Vec2,
     pub end: Vec2,
+    /// named doors other levels can link to, besides start and end
+    pub doors: HashMap<String, Vec2>,
+}
+
+impl Markers {
+    pub fn door(&self, name: &str) -> Option<Vec2> {
+        match name {
+            "start" => Some(self.start),
+            "end" => Some(self.end),
+            _ => self.doors.get(name).copied(),
+        }
+    }
 }
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub struct DrawLayer(usize);
@@ -55,6 +68,7 @@ impl std::default::Default for Markers {
         Self {
             start: vec2(0.0, 0.0),
             end: vec2(0.0, 0.0),
+            doors: HashMap::new(),
         }
     }
 }
@@ -63,8 +77,16 @@ impl LevelInfo {
     pub fn parse(svg: &str) -> Self {
         let mut markers = Markers::default();
         let (size, items) = read_svg(svg);
+        let collectibles = items
+            .iter()
+            .filter(|item| item.color == COLLECTIBLE_COLOR)
+            .count();
+        let outline = LevelOutline::new(&items);
+        let darkness = read_svg_data(svg).get("darkness").map_or(0.0, |darkness| {
+            darkness.parse().expect("data-darkness is not a number")
+        });
         for item in items {
-            if let SvgShape::Circle(circle) = item.shape {
+            if let SvgShape::Circle(circle) = &item.shape {
                 let radius: usize = meter_to_pixel(circle.r).round() as usize;
                 if radius != 50 {
                     continue;
@@ -72,6 +94,10 @@ impl LevelInfo {
                 match item.color {
                     0x00FF00 => markers.start = circle.pos,
                     0xFF0000 => markers.end = circle.pos,
+                    DOOR_COLOR => {
+                        let name = item.data("door").expect("door marker without data-door");
+                        markers.doors.insert(name.to_owned(), circle.pos);
+                    }
                     _ => {}
                 }
             }
@@ -79,6 +105,9 @@ impl LevelInfo {
         Self {
             dims: size,
             markers,
+            collectibles,
+            outline,
+            darkness,
         }
     }
 }
@@ -86,41 +115,100 @@ pub fn load_level(assets: &Assets, world: &mut World, level: LevelId) {
     load_level_at_pos(assets, world, level, vec2(LEVEL_X, LEVEL_Y))
 }
 pub fn load_level_at_pos(assets: &Assets, world: &mut World, level: LevelId, pos: Vec2) {
-    let (_, svg) = &assets.levels[&level.0];
-    let (_, items) = read_svg(svg);
-    for item in items {
+    let prepared = world.streamer.get(assets, level);
+    let tracks: HashMap<String, Track> = prepared
+        .items
+        .iter()
+        .filter_map(|item| Track::from_svg_item(item, pos))
+        .collect();
+    for item in prepared.items.iter() {
+        if item.data("track-id").is_some() {
+            continue;
+        }
         let draw_layer = DrawLayer(item.index);
-        match item.shape {
+        let trigger = Trigger::from_svg_item(item, pos, &world.signals);
+        if let Some(trigger) = trigger.as_ref().filter(|trigger| trigger.is_area()) {
+            world
+                .entities
+                .spawn((trigger.clone(), level, ThingId(item.index), draw_layer));
+            continue;
+        }
+        if let Some(zone) = CameraZone::from_svg_item(item, pos) {
+            world
+                .entities
+                .spawn((zone, level, ThingId(item.index), draw_layer));
+            continue;
+        }
+        if let Some(wind) = Wind::from_svg_item(item, pos) {
+            world
+                .entities
+                .spawn((wind, level, ThingId(item.index), draw_layer));
+            continue;
+        }
+        let track = item.data("track").map(|id| {
+            tracks
+                .get(id)
+                .unwrap_or_else(|| panic!("unknown track: {}", id))
+                .clone()
+        });
+        let (entity, pivot) = match &item.shape {
             SvgShape::Rect(rect) => {
                 let thing_info = ThingInfo::new_rect(rect.pos, rect.rotate, rect.dims, item.color);
                 let thing_id = ThingId(item.index);
-                spawn_thing(assets, world, thing_info, level, thing_id, pos, draw_layer);
+                let entity =
+                    spawn_thing(assets, world, thing_info, level, thing_id, pos, draw_layer);
+                (entity, rect.pos + pos)
             }
             SvgShape::Circle(circle) => {
                 let thing_info =
                     ThingInfo::new_circle(circle.pos, circle.rotate, circle.r, item.color);
                 let thing_id = ThingId(item.index);
-                spawn_thing(assets, world, thing_info, level, thing_id, pos, draw_layer);
+                let entity =
+                    spawn_thing(assets, world, thing_info, level, thing_id, pos, draw_layer);
+                (entity, circle.pos + pos)
             }
             SvgShape::Path(path) => {
-                let material = Material::from_hex_color(item.color);
-                spawn_floor(
-                    assets,
-                    world,
-                    path.vertices,
-                    material,
-                    level,
-                    pos,
-                    draw_layer,
-                );
+                let Some(shape) = prepared.floors.get(&item.index) else {
+                    panic!("unknown floor color: {:x}", item.color);
+                };
+                let pivot = centroid(&path.vertices) + pos;
+                let entity = spawn_floor(world, shape, pos, level, draw_layer);
+                world
+                    .entities
+                    .insert(entity, (ThingId(item.index), ThingPart(0)))
+                    .unwrap();
+                (Some(entity), pivot)
             }
+        };
+        let Some(entity) = entity else {
+            continue;
+        };
+        if let Some(track) = track {
+            attach_platform(world, entity, track, pivot);
+        }
+        // only floors take one-way from their material, things share materials for friction
+        let one_way_material = matches!(item.shape, SvgShape::Path(_))
+            && world
+                .entities
+                .get::<&Material>(entity)
+                .is_ok_and(|material| material.one_way());
+        if item.data("one-way").is_some() || one_way_material {
+            make_one_way(world, entity);
         }
+        if let Some(trigger) = trigger {
+            world.entities.insert_one(entity, trigger).unwrap();
+        }
+        // gates retract downwards by their own height unless told otherwise
+        attach_receiver(world, entity, item, vec2(0.0, item.shape.dims().y));
     }
+    restore_snapshot(world, level, pos);
+    link_bodies(world, level);
     world.levels.insert(level, pos);
 }
 
 pub fn unload_level(world: &mut World, level: LevelId) {
-    world.levels.remove(&level).unwrap();
+    let pos = world.levels.remove(&level).unwrap();
+    take_snapshot(world, level, pos);
     let remove_entities = world
         .entities
         .query_mut::<&LevelId>()
@@ -141,20 +229,21 @@ pub fn unload_level(world: &mut World, level: LevelId) {
     }
 }
 
-pub fn update_loaded_levels_alive(assets: &Assets, world: &mut World) {
-    // only load/unload levels if the player is alive
-    if !world.player.alive() {
-        return;
-    }
-    update_loaded_levels(assets, world);
-}
-
 pub fn update_loaded_levels(assets: &Assets, world: &mut World) {
+    world.streamer.poll();
+    // neighbours start preparing long before their markers come into view
+    let neighbours = world
+        .levels
+        .iter()
+        .flat_map(|(level, pos)| adjacent_levels(assets, *level, *pos))
+        .collect::<Vec<_>>();
+    for (level, _, _) in neighbours {
+        world.streamer.request(assets, level);
+    }
     let levels_to_load = world
         .levels
         .iter()
         .flat_map(|(level, pos)| find_adjacent_levels_to_load(assets, world, *level, *pos))
-        .flatten()
         .collect::<Vec<_>>();
     let levels_to_unload = find_levels_to_unload(assets, world);
     for &(level, pos) in levels_to_load.iter() {
@@ -174,26 +263,39 @@ fn find_adjacent_levels_to_load(
     world: &World,
     level: LevelId,
     pos: Vec2,
-) -> [Option<(LevelId, Vec2)>; 2] {
+) -> Vec<(LevelId, Vec2)> {
+    adjacent_levels(assets, level, pos)
+        .into_iter()
+        .filter(|(_, _, door)| pos_in_camera(world, *door))
+        .map(|(level, pos, _)| (level, pos))
+        .collect()
+}
+
+/// Levels linked to the doors of `level` at `pos`, where they go and the door they meet at.
+pub fn adjacent_levels(assets: &Assets, level: LevelId, pos: Vec2) -> Vec<(LevelId, Vec2, Vec2)> {
     let (info, _) = &assets.levels[&level.0];
+    assets
+        .level_graph
+        .links_from(level)
+        .filter_map(|(door, other)| {
+            let door = pos + info.markers.door(door)?;
+            let (other_info, _) = assets.levels.get(&other.level.0)?;
+            let other_door = other_info.markers.door(&other.name)?;
+            Some((other.level, door - other_door, door))
+        })
+        .collect()
+}
 
-    let load_queries: [(Option<LevelId>, Vec2, fn(&LevelInfo, &LevelInfo) -> Vec2); 2] = [
-        (level.next(assets), info.markers.end, |info, next_info| {
-            info.markers.end - next_info.markers.start
-        }),
-        (level.prev(assets), info.markers.start, |info, prev_info| {
-            info.markers.start - prev_info.markers.end
-        }),
-    ];
-    load_queries.map(|(edge_level, end_pos, new_pos)| {
-        if !pos_in_camera(world, pos + end_pos) {
-            return None;
-        }
-        let edge_level = edge_level?;
-        let (new_info, _) = &assets.levels.get(&edge_level.0)?;
-        let new_pos = pos + new_pos(info, new_info);
-        Some((edge_level, new_pos))
-    })
+/// Loaded level that `pos` is inside of.
+pub fn level_at(assets: &Assets, world: &World, pos: Vec2) -> Option<LevelId> {
+    world
+        .levels
+        .iter()
+        .find(|(level, level_pos)| {
+            let dims = assets.levels[&level.0].0.dims;
+            Rect::new(level_pos.x, level_pos.y, dims.x, dims.y).contains(pos)
+        })
+        .map(|(level, _)| *level)
 }
 
 fn find_levels_to_unload(assets: &Assets, world: &World) -> Vec<LevelId> {
@@ -211,3 +313,30 @@ fn find_levels_to_unload(assets: &Assets, world: &World) -> Vec<LevelId> {
         })
         .collect()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn doors_are_found_by_name() {
+        let info = LevelInfo::parse(
+            r##"<svg width="1000" height="1000" viewBox="0 0 1000 1000" fill="none" xmlns="http://www.w3.org/2000/svg">
+<circle cy="500" r="50" fill="#00FF00"/>
+<circle cx="1000" cy="400" r="50" fill="#FF0000"/>
+<circle cx="600" r="50" fill="#FFB000" data-door="secret"/>
+<circle cx="300" cy="300" r="40" fill="#FFB000" data-door="too-small"/>
+</svg>"##,
+        );
+        let markers = &info.markers;
+        assert_eq!(markers.door("start"), Some(markers.start));
+        assert_eq!(markers.door("end"), Some(markers.end));
+        assert!(markers.end.x > markers.start.x);
+        let secret = markers.door("secret").unwrap();
+        assert!(secret.x > markers.start.x && secret.x < markers.end.x);
+        assert_eq!(secret.y, 0.0);
+        // only marker sized circles are doors
+        assert_eq!(markers.door("too-small"), None);
+        assert_eq!(markers.door("missing"), None);
+    }
+}
@@ -0,0 +1,502 @@
+use std::collections::HashMap;
+
+use macroquad::prelude::*;
+
+use crate::game::assets::Tiles;
+
+use super::draw::floor::{LiquidDraw, TiledDraw};
+use super::floor::{FloorColliders, FloorEdge, FloorShape, Material, VertexDraw};
+use super::level::{LevelInfo, Markers};
+use super::minimap::LevelOutline;
+use super::stream::PreparedLevel;
+use super::svg::{CircleShape, PathShape, RectShape, SvgItem, SvgShape};
+use super::thing::ThingId;
+
+/// Bump whenever the format or anything that goes into a level changes, so old caches are
+/// thrown away instead of misread.
+const LEVEL_CACHE_VERSION: u32 = 3;
+const MAGIC: &[u8; 4] = b"RPLC";
+
+const MATERIALS: [Material; 9] = [
+    Material::Grass,
+    Material::Stone,
+    Material::Water,
+    Material::Mud,
+    Material::Fern,
+    Material::Ice,
+    Material::Honey,
+    Material::Rubber,
+    Material::Leaf,
+];
+
+/// Hash of everything a compiled level depends on, the svg, the tiles and the format.
+fn source_hash(svg: &str, tiles: &Tiles) -> u64 {
+    // fnv-1a, so it stays the same between builds and platforms
+    fn feed(hash: &mut u64, bytes: &[u8]) {
+        for byte in bytes {
+            *hash ^= *byte as u64;
+            *hash = hash.wrapping_mul(0x100000001b3);
+        }
+    }
+    let mut hash = 0xcbf29ce484222325;
+    feed(&mut hash, &LEVEL_CACHE_VERSION.to_le_bytes());
+    feed(&mut hash, svg.as_bytes());
+    let mut tiles = tiles
+        .values()
+        .flat_map(|tile| tile.0.iter())
+        .collect::<Vec<_>>();
+    tiles.sort_unstable_by_key(|(name, _)| name);
+    for (name, constraints) in tiles {
+        feed(&mut hash, name.as_bytes());
+        feed(
+            &mut hash,
+            &[
+                *constraints.left_height.start(),
+                *constraints.left_height.end(),
+                constraints.height,
+                *constraints.right_height.start(),
+                *constraints.right_height.end(),
+                constraints.weight,
+            ],
+        );
+    }
+    hash
+}
+
+/// Compiles a level into the cache format, shapes, floor triangulations, tile choices and
+/// colliders included.
+pub fn encode(svg: &str, tiles: &Tiles, info: &LevelInfo, prepared: &PreparedLevel) -> Vec<u8> {
+    let mut w = Writer(Vec::new());
+    w.0.extend_from_slice(MAGIC);
+    w.u32(LEVEL_CACHE_VERSION);
+    w.u64(source_hash(svg, tiles));
+
+    w.vec2(info.dims);
+    w.vec2(info.markers.start);
+    w.vec2(info.markers.end);
+    let mut doors = info.markers.doors.iter().collect::<Vec<_>>();
+    doors.sort_unstable_by_key(|(name, _)| *name);
+    w.usize(doors.len());
+    for (name, pos) in doors {
+        w.str(name);
+        w.vec2(*pos);
+    }
+    w.usize(info.collectibles);
+    w.usize(info.outline.floors.len());
+    for floor in &info.outline.floors {
+        w.vec2s(floor);
+    }
+    let mut things = info.outline.things.iter().collect::<Vec<_>>();
+    things.sort_unstable_by_key(|(thing_id, _)| thing_id.0);
+    w.usize(things.len());
+    for (thing_id, pos) in things {
+        w.usize(thing_id.0);
+        w.vec2(*pos);
+    }
+    w.f32(info.darkness);
+
+    w.usize(prepared.items.len());
+    for item in &prepared.items {
+        w.item(item);
+    }
+    let mut floors = prepared.floors.iter().collect::<Vec<_>>();
+    floors.sort_unstable_by_key(|(index, _)| **index);
+    w.usize(floors.len());
+    for (index, shape) in floors {
+        w.usize(*index);
+        w.floor(shape);
+    }
+    w.0
+}
+
+/// Reads a compiled level back, `None` if it is corrupt or was compiled from another svg,
+/// other tiles or an older format.
+pub fn decode(bytes: &[u8], svg: &str, tiles: &Tiles) -> Option<(LevelInfo, PreparedLevel)> {
+    let mut r = Reader(bytes);
+    if r.take(4)? != MAGIC || r.u32()? != LEVEL_CACHE_VERSION || r.u64()? != source_hash(svg, tiles)
+    {
+        return None;
+    }
+
+    let dims = r.vec2()?;
+    let markers = Markers {
+        start: r.vec2()?,
+        end: r.vec2()?,
+        doors: (0..r.usize()?)
+            .map(|_| Some((r.str()?, r.vec2()?)))
+            .collect::<Option<HashMap<_, _>>>()?,
+    };
+    let collectibles = r.usize()?;
+    let floors = (0..r.usize()?)
+        .map(|_| r.vec2s())
+        .collect::<Option<Vec<_>>>()?;
+    let things = (0..r.usize()?)
+        .map(|_| Some((ThingId(r.usize()?), r.vec2()?)))
+        .collect::<Option<HashMap<_, _>>>()?;
+    let darkness = r.f32()?;
+    let info = LevelInfo {
+        dims,
+        markers,
+        collectibles,
+        outline: LevelOutline { floors, things },
+        darkness,
+    };
+
+    let items = (0..r.usize()?)
+        .map(|_| r.item())
+        .collect::<Option<Vec<_>>>()?;
+    let floors = (0..r.usize()?)
+        .map(|_| Some((r.usize()?, r.floor()?)))
+        .collect::<Option<HashMap<_, _>>>()?;
+    if !r.0.is_empty() {
+        return None;
+    }
+    Some((info, PreparedLevel { items, floors }))
+}
+
+struct Writer(Vec<u8>);
+
+impl Writer {
+    fn u8(&mut self, value: u8) {
+        self.0.push(value);
+    }
+    fn u32(&mut self, value: u32) {
+        self.0.extend_from_slice(&value.to_le_bytes());
+    }
+    fn u64(&mut self, value: u64) {
+        self.0.extend_from_slice(&value.to_le_bytes());
+    }
+    fn usize(&mut self, value: usize) {
+        self.u32(value as u32);
+    }
+    fn f32(&mut self, value: f32) {
+        self.0.extend_from_slice(&value.to_le_bytes());
+    }
+    fn vec2(&mut self, value: Vec2) {
+        self.f32(value.x);
+        self.f32(value.y);
+    }
+    fn vec2s(&mut self, values: &[Vec2]) {
+        self.usize(values.len());
+        for value in values {
+            self.vec2(*value);
+        }
+    }
+    fn indices(&mut self, indices: &[[u32; 3]]) {
+        self.usize(indices.len());
+        for triangle in indices {
+            for index in triangle {
+                self.u32(*index);
+            }
+        }
+    }
+    fn str(&mut self, value: &str) {
+        self.usize(value.len());
+        self.0.extend_from_slice(value.as_bytes());
+    }
+    fn color(&mut self, color: Color) {
+        for channel in [color.r, color.g, color.b, color.a] {
+            self.f32(channel);
+        }
+    }
+    fn rect(&mut self, rect: Rect) {
+        for value in [rect.x, rect.y, rect.w, rect.h] {
+            self.f32(value);
+        }
+    }
+    fn item(&mut self, item: &SvgItem) {
+        match &item.shape {
+            SvgShape::Rect(rect) => {
+                self.u8(0);
+                self.vec2(rect.pos);
+                self.vec2(rect.dims);
+                self.f32(rect.rotate);
+            }
+            SvgShape::Path(path) => {
+                self.u8(1);
+                self.vec2s(&path.vertices);
+            }
+            SvgShape::Circle(circle) => {
+                self.u8(2);
+                self.vec2(circle.pos);
+                self.f32(circle.r);
+                self.f32(circle.rotate);
+            }
+        }
+        self.u32(item.color);
+        self.usize(item.index);
+        let mut data = item.data.iter().collect::<Vec<_>>();
+        data.sort_unstable();
+        self.usize(data.len());
+        for (key, value) in data {
+            self.str(key);
+            self.str(value);
+        }
+    }
+    fn floor(&mut self, shape: &FloorShape) {
+        let material = MATERIALS.iter().position(|m| *m == shape.material).unwrap();
+        self.u8(material as u8);
+        match &shape.vertex_draw {
+            VertexDraw::Tiled(tiled_draw) => {
+                self.u8(0);
+                self.str(&tiled_draw.tile);
+                for color in tiled_draw.colors {
+                    self.color(color);
+                }
+                for (vertices, indices) in &tiled_draw.trimeshes {
+                    self.vec2s(vertices);
+                    self.indices(indices);
+                }
+                self.usize(tiled_draw.tile_textures.len());
+                for (rect, left_offset, textures) in &tiled_draw.tile_textures {
+                    self.rect(*rect);
+                    self.f32(*left_offset);
+                    self.usize(textures.len());
+                    for texture in textures {
+                        self.str(texture);
+                    }
+                }
+            }
+            VertexDraw::Liquid(liquid_draw) => {
+                self.u8(1);
+                self.color(liquid_draw.color);
+                self.color(liquid_draw.surface_color);
+                self.vec2s(&liquid_draw.vertices);
+                self.indices(&liquid_draw.indices);
+                self.usize(liquid_draw.surface.len());
+                for index in &liquid_draw.surface {
+                    self.usize(*index);
+                }
+            }
+        }
+        match &shape.colliders {
+            FloorColliders::Lazy(edges) => {
+                self.u8(0);
+                self.usize(edges.len());
+                for edge in edges {
+                    self.rect(edge.rect);
+                    self.vec2(edge.pos);
+                    self.f32(edge.rotation);
+                    self.vec2(edge.half_extents);
+                }
+            }
+            FloorColliders::Sensor(vertices, indices) => {
+                self.u8(1);
+                self.vec2s(vertices);
+                self.indices(indices);
+            }
+        }
+    }
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl Reader<'_> {
+    fn take(&mut self, len: usize) -> Option<&[u8]> {
+        if self.0.len() < len {
+            return None;
+        }
+        let (taken, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Some(taken)
+    }
+    fn u8(&mut self) -> Option<u8> {
+        Some(self.take(1)?[0])
+    }
+    fn u32(&mut self) -> Option<u32> {
+        Some(u32::from_le_bytes(self.take(4)?.try_into().ok()?))
+    }
+    fn u64(&mut self) -> Option<u64> {
+        Some(u64::from_le_bytes(self.take(8)?.try_into().ok()?))
+    }
+    fn usize(&mut self) -> Option<usize> {
+        Some(self.u32()? as usize)
+    }
+    fn f32(&mut self) -> Option<f32> {
+        Some(f32::from_le_bytes(self.take(4)?.try_into().ok()?))
+    }
+    fn vec2(&mut self) -> Option<Vec2> {
+        Some(vec2(self.f32()?, self.f32()?))
+    }
+    fn vec2s(&mut self) -> Option<Vec<Vec2>> {
+        (0..self.usize()?).map(|_| self.vec2()).collect()
+    }
+    fn indices(&mut self) -> Option<Vec<[u32; 3]>> {
+        (0..self.usize()?)
+            .map(|_| Some([self.u32()?, self.u32()?, self.u32()?]))
+            .collect()
+    }
+    fn str(&mut self) -> Option<String> {
+        let len = self.usize()?;
+        String::from_utf8(self.take(len)?.to_vec()).ok()
+    }
+    fn color(&mut self) -> Option<Color> {
+        Some(Color::new(
+            self.f32()?,
+            self.f32()?,
+            self.f32()?,
+            self.f32()?,
+        ))
+    }
+    fn rect(&mut self) -> Option<Rect> {
+        Some(Rect::new(
+            self.f32()?,
+            self.f32()?,
+            self.f32()?,
+            self.f32()?,
+        ))
+    }
+    fn item(&mut self) -> Option<SvgItem> {
+        let shape = match self.u8()? {
+            0 => SvgShape::Rect(RectShape {
+                pos: self.vec2()?,
+                dims: self.vec2()?,
+                rotate: self.f32()?,
+            }),
+            1 => SvgShape::Path(PathShape {
+                vertices: self.vec2s()?,
+            }),
+            2 => SvgShape::Circle(CircleShape {
+                pos: self.vec2()?,
+                r: self.f32()?,
+                rotate: self.f32()?,
+            }),
+            _ => return None,
+        };
+        let color = self.u32()?;
+        let index = self.usize()?;
+        let data = (0..self.usize()?)
+            .map(|_| Some((self.str()?, self.str()?)))
+            .collect::<Option<HashMap<_, _>>>()?;
+        Some(SvgItem {
+            shape,
+            color,
+            index,
+            data,
+        })
+    }
+    fn floor(&mut self) -> Option<FloorShape> {
+        let material = *MATERIALS.get(self.u8()? as usize)?;
+        let vertex_draw = match self.u8()? {
+            0 => {
+                let tile = self.str()?;
+                let colors = [self.color()?, self.color()?, self.color()?];
+                let trimeshes = [
+                    (self.vec2s()?, self.indices()?),
+                    (self.vec2s()?, self.indices()?),
+                    (self.vec2s()?, self.indices()?),
+                ];
+                let tile_textures = (0..self.usize()?)
+                    .map(|_| {
+                        let rect = self.rect()?;
+                        let left_offset = self.f32()?;
+                        let textures = (0..self.usize()?)
+                            .map(|_| self.str())
+                            .collect::<Option<Vec<_>>>()?;
+                        Some((rect, left_offset, textures))
+                    })
+                    .collect::<Option<Vec<_>>>()?;
+                VertexDraw::Tiled(TiledDraw {
+                    tile,
+                    colors,
+                    trimeshes,
+                    tile_textures,
+                })
+            }
+            1 => VertexDraw::Liquid(LiquidDraw {
+                color: self.color()?,
+                surface_color: self.color()?,
+                vertices: self.vec2s()?,
+                indices: self.indices()?,
+                surface: (0..self.usize()?)
+                    .map(|_| self.usize())
+                    .collect::<Option<Vec<_>>>()?,
+            }),
+            _ => return None,
+        };
+        let colliders = match self.u8()? {
+            0 => FloorColliders::Lazy(
+                (0..self.usize()?)
+                    .map(|_| {
+                        Some(FloorEdge {
+                            rect: self.rect()?,
+                            pos: self.vec2()?,
+                            rotation: self.f32()?,
+                            half_extents: self.vec2()?,
+                        })
+                    })
+                    .collect::<Option<Vec<_>>>()?,
+            ),
+            1 => FloorColliders::Sensor(self.vec2s()?, self.indices()?),
+            _ => return None,
+        };
+        Some(FloorShape {
+            material,
+            vertex_draw,
+            colliders,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::assets::{LEVEL_FILENAMES, texture_tiles};
+
+    fn compile(svg: &str, tiles: &Tiles) -> Vec<u8> {
+        let info = LevelInfo::parse(svg);
+        let prepared = PreparedLevel::new(tiles, svg);
+        encode(svg, tiles, &info, &prepared)
+    }
+
+    fn level_svg(filename: &str) -> String {
+        std::fs::read_to_string(format!("assets/levels/{}", filename)).unwrap()
+    }
+
+    #[test]
+    fn round_trip() {
+        let tiles = texture_tiles();
+        for filename in LEVEL_FILENAMES {
+            let svg = level_svg(filename);
+            let bytes = compile(&svg, &tiles);
+            let (info, prepared) = decode(&bytes, &svg, &tiles).unwrap();
+            let parsed = LevelInfo::parse(&svg);
+            assert_eq!(info.dims, parsed.dims);
+            assert_eq!(info.markers.start, parsed.markers.start);
+            assert_eq!(info.markers.end, parsed.markers.end);
+            assert_eq!(info.collectibles, parsed.collectibles);
+            assert_eq!(
+                prepared.items.len(),
+                PreparedLevel::new(&tiles, &svg).items.len()
+            );
+            // nothing is lost, so encoding what was decoded gives the same bytes
+            assert_eq!(encode(&svg, &tiles, &info, &prepared), bytes);
+        }
+    }
+
+    #[test]
+    fn stale_or_corrupt_is_rejected() {
+        let tiles = texture_tiles();
+        let svg = level_svg(LEVEL_FILENAMES[0]);
+        let bytes = compile(&svg, &tiles);
+        assert!(decode(&bytes, &format!("{} ", svg), &tiles).is_none());
+        assert!(decode(&bytes[..bytes.len() - 1], &svg, &tiles).is_none());
+        assert!(decode(&[], &svg, &tiles).is_none());
+    }
+
+    /// Fails when a level or tile changed without running `--compile-levels`.
+    #[test]
+    fn committed_caches_are_fresh() {
+        let tiles = texture_tiles();
+        for filename in LEVEL_FILENAMES {
+            let svg = level_svg(filename);
+            let cache = format!("assets/levels/{}", filename.replace(".svg", ".bin"));
+            let bytes = std::fs::read(&cache).unwrap_or_else(|_| panic!("{} is missing", cache));
+            assert!(
+                decode(&bytes, &svg, &tiles).is_some(),
+                "{} is stale, run with --compile-levels",
+                cache
+            );
+        }
+    }
+}
@@ -0,0 +1,244 @@
+use super::level::{LevelId, LevelInfo};
+
+/// One end of a link between levels, a named door marker in a level.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Door {
+    pub level: LevelId,
+    pub name: String,
+}
+
+impl Door {
+    /// `level:door`, like `3:secret`.
+    fn parse(door: &str) -> Self {
+        let (level, name) = door
+            .split_once(':')
+            .unwrap_or_else(|| panic!("door {} is not level:door", door));
+        Self {
+            level: LevelId(
+                level
+                    .parse()
+                    .unwrap_or_else(|_| panic!("door {} has no level number", door)),
+            ),
+            name: name.to_owned(),
+        }
+    }
+}
+
+/// How levels connect, every link goes both ways and lines the two doors up. Links are
+/// written in the order the levels are played, from the earlier level to the later one.
+#[derive(Debug, Clone, Default)]
+pub struct LevelGraph {
+    links: Vec<(Door, Door)>,
+}
+
+impl LevelGraph {
+    /// One link per line, `0:end 1:start`, `#` starts a comment.
+    pub fn parse(graph: &str) -> Self {
+        let links = graph
+            .lines()
+            .map(|line| line.split('#').next().unwrap().trim())
+            .filter(|line| !line.is_empty())
+            .map(|line| {
+                let (from, to) = line
+                    .split_once(char::is_whitespace)
+                    .unwrap_or_else(|| panic!("level link {} needs two doors", line));
+                (Door::parse(from.trim()), Door::parse(to.trim()))
+            })
+            .collect();
+        Self { links }
+    }
+
+    /// Every level's end leads to the next level's start, for when there's no graph.
+    pub fn linear(levels: &[usize]) -> Self {
+        let mut levels = levels.to_vec();
+        levels.sort_unstable();
+        let links = levels
+            .windows(2)
+            .map(|pair| {
+                let door = |level, name: &str| Door {
+                    level: LevelId(level),
+                    name: name.to_owned(),
+                };
+                (door(pair[0], "end"), door(pair[1], "start"))
+            })
+            .collect();
+        Self { links }
+    }
+
+    /// Panics on links to levels or doors that don't exist.
+    pub fn validate<'a>(&self, level_info: impl Fn(LevelId) -> Option<&'a LevelInfo>) {
+        for door in self.links.iter().flat_map(|(from, to)| [from, to]) {
+            let info = level_info(door.level)
+                .unwrap_or_else(|| panic!("level link to unknown level {}", door.level.0));
+            if info.markers.door(&door.name).is_none() {
+                panic!("level {} has no door {}", door.level.0, door.name);
+            }
+        }
+    }
+
+    /// Levels that links written from `level` lead on to, the way the levels are played.
+    pub fn next_levels(&self, level: LevelId) -> impl Iterator<Item = LevelId> {
+        self.links
+            .iter()
+            .filter(move |(from, to)| from.level == level && to.level != level)
+            .map(|(_, to)| to.level)
+    }
+
+    /// Doors of `level` with the door they lead to.
+    pub fn links_from(&self, level: LevelId) -> impl Iterator<Item = (&str, &Door)> {
+        self.links.iter().filter_map(move |(from, to)| {
+            if from.level == level {
+                Some((from.name.as_str(), to))
+            } else if to.level == level {
+                Some((to.name.as_str(), from))
+            } else {
+                None
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::game::assets::LEVEL_FILENAMES;
+
+    fn door(level: usize, name: &str) -> Door {
+        Door {
+            level: LevelId(level),
+            name: name.to_owned(),
+        }
+    }
+
+    /// Level with start and end markers and a door marker for each of `doors`.
+    fn level_info(doors: &[&str]) -> LevelInfo {
+        let doors = doors
+            .iter()
+            .map(|name| {
+                format!(
+                    r##"<circle cx="500" r="50" fill="#FFB000" data-door="{}"/>"##,
+                    name
+                )
+            })
+            .collect::<String>();
+        LevelInfo::parse(&format!(
+            r##"<svg width="1000" height="1000" viewBox="0 0 1000 1000" fill="none" xmlns="http://www.w3.org/2000/svg">
+<circle cy="500" r="50" fill="#00FF00"/>
+<circle cx="1000" cy="500" r="50" fill="#FF0000"/>
+{}
+</svg>"##,
+            doors
+        ))
+    }
+
+    #[test]
+    fn parse_skips_comments_and_blank_lines() {
+        let graph = LevelGraph::parse(
+            "# main path\n0:end 1:start\n\n1:end   2:start # trailing\n1:secret 3:start\n",
+        );
+        assert_eq!(
+            graph.links,
+            vec![
+                (door(0, "end"), door(1, "start")),
+                (door(1, "end"), door(2, "start")),
+                (door(1, "secret"), door(3, "start")),
+            ]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "needs two doors")]
+    fn parse_panics_on_one_door() {
+        LevelGraph::parse("0:end");
+    }
+
+    #[test]
+    #[should_panic(expected = "is not level:door")]
+    fn parse_panics_on_door_without_level() {
+        LevelGraph::parse("0:end start");
+    }
+
+    #[test]
+    fn links_go_both_ways() {
+        let graph = LevelGraph::parse("0:end 1:start\n1:end 2:start\n1:secret 3:start");
+        let links = graph.links_from(LevelId(1)).collect::<Vec<_>>();
+        assert_eq!(
+            links,
+            vec![
+                ("start", &door(0, "end")),
+                ("end", &door(2, "start")),
+                ("secret", &door(3, "start")),
+            ]
+        );
+        assert_eq!(graph.links_from(LevelId(4)).count(), 0);
+        let next = graph.next_levels(LevelId(1)).collect::<Vec<_>>();
+        assert_eq!(next, vec![LevelId(2), LevelId(3)]);
+    }
+
+    #[test]
+    fn linear_chains_sorted_levels() {
+        let graph = LevelGraph::linear(&[2, 0, 1]);
+        assert_eq!(
+            graph.links,
+            vec![
+                (door(0, "end"), door(1, "start")),
+                (door(1, "end"), door(2, "start")),
+            ]
+        );
+    }
+
+    #[test]
+    fn branches_validate_against_level_doors() {
+        let graph = LevelGraph::parse("0:end 1:start\n0:west 2:east\n1:end 2:start");
+        let levels = HashMap::from([
+            (0, level_info(&["west"])),
+            (1, level_info(&[])),
+            (2, level_info(&["east"])),
+        ]);
+        graph.validate(|level| levels.get(&level.0));
+        let next = graph.next_levels(LevelId(0)).collect::<Vec<_>>();
+        assert_eq!(next, vec![LevelId(1), LevelId(2)]);
+        let links = graph.links_from(LevelId(2)).collect::<Vec<_>>();
+        assert_eq!(
+            links,
+            vec![("east", &door(0, "west")), ("start", &door(1, "end"))]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "level 2 has no door east")]
+    fn validate_panics_on_missing_door() {
+        let graph = LevelGraph::parse("0:end 1:start\n0:west 2:east");
+        let levels = HashMap::from([
+            (0, level_info(&["west"])),
+            (1, level_info(&[])),
+            (2, level_info(&["west"])),
+        ]);
+        graph.validate(|level| levels.get(&level.0));
+    }
+
+    #[test]
+    fn committed_graph_is_valid_and_branches() {
+        let graph = LevelGraph::parse(&std::fs::read_to_string("assets/levels/graph.txt").unwrap());
+        let levels = LEVEL_FILENAMES
+            .iter()
+            .map(|filename| {
+                let level = filename
+                    .strip_suffix(".svg")
+                    .unwrap()
+                    .parse::<usize>()
+                    .unwrap();
+                let svg = std::fs::read_to_string(format!("assets/levels/{}", filename)).unwrap();
+                (level, LevelInfo::parse(&svg))
+            })
+            .collect::<HashMap<_, _>>();
+        graph.validate(|level| levels.get(&level.0));
+        assert!(
+            levels
+                .keys()
+                .any(|level| graph.next_levels(LevelId(*level)).count() > 1)
+        );
+    }
+}
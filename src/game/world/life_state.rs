@@ -5,3 +5,9 @@ pub enum LifeState {
     Alive(Transition),
     Dead(Transition),
 }
+
+impl LifeState {
+    pub fn alive(&self) -> bool {
+        matches!(self, LifeState::Alive(Transition::End))
+    }
+}
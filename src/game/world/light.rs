@@ -1,16 +1,44 @@
+use std::collections::HashMap;
+
 use macroquad::prelude::*;
 
 use crate::{
-    consts::{LIGHT_FLICKER_GROW_SPEED, LIGHT_FLICKER_INTERVAL},
+    consts::{
+        LIGHT_FLICKER_GROW_SPEED, LIGHT_FLICKER_INTERVAL, LIGHT_MAP_FALLOFF, LIGHT_MAP_MAX_FALLOFF,
+    },
     game::{assets::Assets, world::draw::pixel_to_meter},
 };
 
-use super::svg::{SvgShape, read_svg};
+use super::svg::{SvgItem, SvgShape, read_svg, read_svg_data};
 
+/// A glowing circle of a light svg. Besides the fill color, circles (or the svg tag, for
+/// all of its circles) can set `data-intensity`, `data-falloff` (0 to 4),
+/// `data-flicker-interval` (average seconds between flickers), `data-flicker-speed` and
+/// `data-pulse` (per second).
 #[derive(Debug, Clone)]
 pub struct Light {
     pub pos: Vec2,
     pub radius: f32,
+    pub color: Color,
+    pub intensity: f32,
+    /// how fast the light fades towards the edge of its reach in the light map
+    pub falloff: f32,
+    pub flicker_interval: f32,
+    pub flicker_speed: f32,
+    /// 0 is a steady light
+    pub pulse: f32,
+}
+
+impl Light {
+    /// Strength of the light in `state` at `time`, with intensity and pulse applied.
+    pub fn brightness(&self, state: &LightState, time: f32) -> f32 {
+        let pulse = if self.pulse > 0.0 {
+            0.75 + 0.25 * (time * self.pulse * std::f32::consts::TAU).sin()
+        } else {
+            1.0
+        };
+        state.strength() * self.intensity * pulse
+    }
 }
 #[derive(Debug, Clone)]
 pub struct Ripple {
@@ -29,6 +57,8 @@ impl Ripple {
 #[derive(Debug, Clone)]
 pub struct LightGroup {
     pub lights: Vec<(Light, LightState)>,
+    /// switched off by signals
+    pub enabled: bool,
 }
 #[derive(Debug, Clone)]
 pub enum LightState {
@@ -45,10 +75,9 @@ pub enum FlickerState {
 pub struct RippleState {
     pub strength: f32,
 }
+/// Ripples whenever the player starts touching it.
 #[derive(Debug, Clone)]
-pub struct RippleSource {
-    pub just_contacted: bool,
-}
+pub struct RippleSource;
 impl RippleState {
     pub fn update(&mut self, dt: f32, pos: Vec2, ripples: &[Ripple]) {
         let rippled = ripples
@@ -63,10 +92,10 @@ impl RippleState {
     }
 }
 impl FlickerState {
-    pub fn update(&mut self, dt: f32) {
+    pub fn update(&mut self, dt: f32, light: &Light) {
         *self = match self {
             Self::Growing(strength) => {
-                let new_strength = *strength + LIGHT_FLICKER_GROW_SPEED * dt;
+                let new_strength = *strength + light.flicker_speed * dt;
                 if new_strength > 1.0 {
                     Self::Shrinking(1.0 - (new_strength - 1.0))
                 } else {
@@ -74,7 +103,7 @@ impl FlickerState {
                 }
             }
             Self::Shrinking(strength) => {
-                let new_strength = *strength - LIGHT_FLICKER_GROW_SPEED * dt;
+                let new_strength = *strength - light.flicker_speed * dt;
                 if new_strength < 0.0 {
                     Self::Off
                 } else {
@@ -82,7 +111,7 @@ impl FlickerState {
                 }
             }
             Self::Off => {
-                let prob = get_frame_time() / LIGHT_FLICKER_INTERVAL;
+                let prob = dt / light.flicker_interval;
                 let start_flicker = rand::gen_range(0.0, 1.0) < prob;
                 if start_flicker {
                     Self::Growing(0.0)
@@ -105,32 +134,56 @@ impl LightState {
     }
 }
 
-pub fn shape_to_light(shape: &SvgShape) -> Light {
-    match shape {
-        SvgShape::Circle(circle) => {
-            // we don't care about the rotation of the circle
-            Light {
-                pos: circle.pos,
-                radius: circle.r,
-            }
-        }
-        _ => panic!("Only circles are supported for lights"),
+pub fn item_to_light(item: &SvgItem, defaults: &HashMap<String, String>) -> Light {
+    let SvgShape::Circle(circle) = &item.shape else {
+        panic!("Only circles are supported for lights");
+    };
+    let attr = |key: &str, default: f32| {
+        item.data_f32(key).unwrap_or_else(|| {
+            defaults.get(key).map_or(default, |value| {
+                value
+                    .parse()
+                    .unwrap_or_else(|_| panic!("data-{} attribute is not a number", key))
+            })
+        })
+    };
+    let falloff = attr("falloff", LIGHT_MAP_FALLOFF);
+    if !(0.0..=LIGHT_MAP_MAX_FALLOFF).contains(&falloff) {
+        panic!(
+            "data-falloff {} is outside 0 to {}",
+            falloff, LIGHT_MAP_MAX_FALLOFF
+        );
+    }
+    // we don't care about the rotation of the circle
+    Light {
+        pos: circle.pos,
+        radius: circle.r,
+        color: Color::from_hex(item.color),
+        intensity: attr("intensity", 1.0),
+        falloff,
+        flicker_interval: attr("flicker-interval", LIGHT_FLICKER_INTERVAL),
+        flicker_speed: attr("flicker-speed", LIGHT_FLICKER_GROW_SPEED),
+        pulse: attr("pulse", 0.0),
     }
 }
 
 pub fn load_light(assets: &Assets, light: &str, init_state: LightState) -> LightGroup {
     let svg = &assets.lights[light];
     let (size, items) = read_svg(svg);
+    let defaults = read_svg_data(svg);
     let lights = items
-        .into_iter()
-        .map(|item| shape_to_light(&item.shape))
+        .iter()
+        .map(|item| item_to_light(item, &defaults))
         .map(|light| Light {
             pos: light.pos - size / 2.0,
             ..light
         })
         .map(|light| (light, init_state.clone()))
         .collect::<Vec<_>>();
-    LightGroup { lights }
+    LightGroup {
+        lights,
+        enabled: true,
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -0,0 +1,62 @@
+use std::collections::HashMap;
+
+use macroquad::prelude::*;
+
+use crate::consts::*;
+use crate::game::assets::Assets;
+
+use super::World;
+use super::level::{LevelId, adjacent_levels};
+use super::polygon::simplify_polygon;
+use super::svg::{SvgItem, SvgShape};
+use super::thing::ThingId;
+
+/// Simplified look of a level for the minimap, with positions relative to the level.
+#[derive(Debug, Clone, Default)]
+pub struct LevelOutline {
+    pub floors: Vec<Vec<Vec2>>,
+    /// center of every rect and circle, so respawns can be found by their thing id
+    pub things: HashMap<ThingId, Vec2>,
+}
+
+impl LevelOutline {
+    pub fn new(items: &[SvgItem]) -> Self {
+        let mut outline = Self::default();
+        for item in items {
+            let invisible = item.data("trigger").is_some() || item.data("camera").is_some();
+            if item.data("track-id").is_some() || invisible {
+                continue;
+            }
+            match &item.shape {
+                SvgShape::Path(path) if item.color != WIND_COLOR => {
+                    outline
+                        .floors
+                        .push(simplify_polygon(&path.vertices, MINIMAP_SIMPLIFY));
+                }
+                SvgShape::Path(_) => {}
+                SvgShape::Rect(rect) => {
+                    outline.things.insert(ThingId(item.index), rect.pos);
+                }
+                SvgShape::Circle(circle) => {
+                    outline.things.insert(ThingId(item.index), circle.pos);
+                }
+            }
+        }
+        outline
+    }
+}
+
+/// Loaded levels and their direct neighbours, with whether they are loaded.
+pub fn nearby_levels(assets: &Assets, world: &World) -> HashMap<LevelId, (Vec2, bool)> {
+    let mut levels: HashMap<LevelId, (Vec2, bool)> = world
+        .levels
+        .iter()
+        .map(|(level, pos)| (*level, (*pos, true)))
+        .collect();
+    for (level, pos) in world.levels.iter() {
+        for (adjacent, adjacent_pos, _) in adjacent_levels(assets, *level, *pos) {
+            levels.entry(adjacent).or_insert((adjacent_pos, false));
+        }
+    }
+    levels
+}
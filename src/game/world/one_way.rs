@@ -0,0 +1,71 @@
+use hecs::Entity;
+use rapier2d::prelude::*;
+
+use crate::consts::*;
+
+use super::World;
+use super::floor::LazyCollider;
+
+/// state `update_as_oneway_platform` keeps in the manifold while contacts are ignored
+const CONTACT_FORBIDDEN: u32 = 2;
+
+/// Lets bodies pass through one-way platforms unless they come from above. One-way colliders
+/// are the ones with `ActiveHooks::MODIFY_SOLVER_CONTACTS`, and block along their body's up.
+#[derive(Debug, Clone, Default)]
+pub struct OneWayHooks {
+    /// body that falls through every one-way platform, polly while dropping
+    pub dropping: Option<RigidBodyHandle>,
+}
+
+impl PhysicsHooks for OneWayHooks {
+    fn modify_solver_contacts(&self, context: &mut ContactModificationContext) {
+        let colliders = context.colliders;
+        let one_way = |handle: ColliderHandle| {
+            colliders[handle]
+                .active_hooks()
+                .contains(ActiveHooks::MODIFY_SOLVER_CONTACTS)
+        };
+        // the normal points out of collider1, so it's flipped when the platform is collider2
+        let (platform, sign, other_body) = if one_way(context.collider1) {
+            (context.collider1, 1.0, context.rigid_body2)
+        } else {
+            (context.collider2, -1.0, context.rigid_body1)
+        };
+        if other_body.is_some() && other_body == self.dropping {
+            // stays forbidden until the body is out of the platform, like coming from below
+            context.solver_contacts.clear();
+            *context.user_data = CONTACT_FORBIDDEN;
+            return;
+        }
+        let platform = &colliders[platform];
+        let rotation = match platform.parent() {
+            Some(body) => *context.bodies[body].rotation(),
+            None => platform.position().rotation,
+        };
+        let up = rotation * vector![0.0, -sign];
+        let allowed_local_n1 = colliders[context.collider1].position().rotation.inverse() * up;
+        context.update_as_oneway_platform(&allowed_local_n1, ONE_WAY_ANGLE);
+    }
+}
+
+pub fn one_way_collider(builder: ColliderBuilder) -> ColliderBuilder {
+    builder.active_hooks(ActiveHooks::MODIFY_SOLVER_CONTACTS)
+}
+
+/// Turns every collider of `entity` into a one-way platform, including lazy colliders that
+/// aren't built yet. Used for `data-one-way` and one-way materials.
+pub fn make_one_way(world: &mut World, entity: Entity) {
+    let Ok(body_handle) = world.entities.get::<&RigidBodyHandle>(entity).map(|h| *h) else {
+        return;
+    };
+    let body = world.physics_world.get_body(body_handle).unwrap();
+    for collider in body.colliders().to_vec() {
+        let collider = world.physics_world.get_collider_mut(collider).unwrap();
+        collider.set_active_hooks(collider.active_hooks() | ActiveHooks::MODIFY_SOLVER_CONTACTS);
+    }
+    for (_, lazy_collider) in world.entities.query_mut::<&mut LazyCollider>() {
+        if lazy_collider.body_handle == body_handle {
+            lazy_collider.builder = one_way_collider(lazy_collider.builder.clone());
+        }
+    }
+}
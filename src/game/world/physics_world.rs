@@ -1,6 +1,9 @@
 use macroquad::time::get_frame_time;
+use rapier2d::crossbeam::channel::{Receiver, unbounded};
 use rapier2d::prelude::*;
 
+use super::one_way::OneWayHooks;
+
 pub struct PhysicsWorld {
     pub rigid_body_set: RigidBodySet,
     pub collider_set: ColliderSet,
@@ -13,8 +16,11 @@ pub struct PhysicsWorld {
     pub impulse_joint_set: ImpulseJointSet,
     pub multibody_joint_set: MultibodyJointSet,
     pub ccd_solver: CCDSolver,
-    pub physics_hooks: (),
-    pub event_handler: (),
+    pub physics_hooks: OneWayHooks,
+    pub event_handler: ChannelEventCollector,
+    /// filled by the event handler during `update`, see `events::update_events`
+    pub collision_events: Receiver<CollisionEvent>,
+    pub contact_force_events: Receiver<ContactForceEvent>,
 }
 
 impl Default for PhysicsWorld {
@@ -37,8 +43,10 @@ impl PhysicsWorld {
         let impulse_joint_set = ImpulseJointSet::new();
         let multibody_joint_set = MultibodyJointSet::new();
         let ccd_solver = CCDSolver::new();
-        let physics_hooks = ();
-        let event_handler = ();
+        let physics_hooks = OneWayHooks::default();
+        let (collision_send, collision_events) = unbounded();
+        let (contact_force_send, contact_force_events) = unbounded();
+        let event_handler = ChannelEventCollector::new(collision_send, contact_force_send);
 
         Self {
             rigid_body_set,
@@ -54,6 +62,8 @@ impl PhysicsWorld {
             ccd_solver,
             physics_hooks,
             event_handler,
+            collision_events,
+            contact_force_events,
         }
     }
 
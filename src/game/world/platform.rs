@@ -0,0 +1,205 @@
+use hecs::Entity;
+use itertools::Itertools;
+use macroquad::prelude::*;
+use nalgebra::{Isometry2, Translation2, UnitComplex};
+use rapier2d::prelude::*;
+
+use super::World;
+use super::draw::pixel_to_meter;
+use super::svg::{SvgItem, SvgShape};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrackMode {
+    /// go around the closed track
+    Loop,
+    /// go back and forth along the open track
+    PingPong,
+}
+
+/// Path marker in a level that moving platforms follow.
+///
+/// Authored as a path or line with `data-track-id`, and optionally `data-speed` (px/s),
+/// `data-rotate` (degrees/s) and `data-mode` (`loop` or `ping-pong`).
+#[derive(Debug, Clone)]
+pub struct Track {
+    pub points: Vec<Vec2>,
+    pub mode: TrackMode,
+    pub speed: f32,
+    pub angular_speed: f32,
+}
+
+impl Track {
+    pub fn from_svg_item(item: &SvgItem, pos: Vec2) -> Option<(String, Self)> {
+        let id = item.data("track-id")?;
+        let SvgShape::Path(path) = &item.shape else {
+            panic!("track {} must be a path or line", id);
+        };
+        let mode = match item.data("mode") {
+            Some("loop") => TrackMode::Loop,
+            Some("ping-pong") | None => TrackMode::PingPong,
+            Some(mode) => panic!("unknown track mode: {}", mode),
+        };
+        let track = Self {
+            points: path.vertices.iter().map(|v| *v + pos).collect(),
+            mode,
+            speed: pixel_to_meter(item.data_f32("speed").unwrap_or(100.0)),
+            angular_speed: item.data_f32("rotate").unwrap_or(0.0).to_radians(),
+        };
+        Some((id.to_owned(), track))
+    }
+
+    fn segments(&self) -> Vec<(Vec2, Vec2)> {
+        match self.mode {
+            TrackMode::Loop => self
+                .points
+                .iter()
+                .copied()
+                .circular_tuple_windows()
+                .collect(),
+            TrackMode::PingPong => self.points.iter().copied().tuple_windows().collect(),
+        }
+    }
+
+    pub fn length(&self) -> f32 {
+        self.segments().iter().map(|(a, b)| a.distance(*b)).sum()
+    }
+
+    /// Point at `distance` along the track, wrapping or bouncing at the end depending on the mode.
+    pub fn point_at(&self, distance: f32) -> Vec2 {
+        let length = self.length();
+        if length <= 0.0 {
+            return self.points[0];
+        }
+        let mut distance = match self.mode {
+            TrackMode::Loop => distance.rem_euclid(length),
+            TrackMode::PingPong => length - (distance.rem_euclid(length * 2.0) - length).abs(),
+        };
+        for (a, b) in self.segments() {
+            let segment = a.distance(b);
+            if distance <= segment {
+                return a.lerp(b, distance / segment.max(f32::EPSILON));
+            }
+            distance -= segment;
+        }
+        *self.points.last().unwrap()
+    }
+
+    /// Distance along the track of the point closest to `pos`.
+    pub fn closest_distance(&self, pos: Vec2) -> f32 {
+        let mut travelled = 0.0;
+        let mut closest = (f32::INFINITY, 0.0);
+        for (a, b) in self.segments() {
+            let segment = a.distance(b);
+            let t = ((pos - a).dot(b - a) / (segment * segment).max(f32::EPSILON)).clamp(0.0, 1.0);
+            let distance = pos.distance(a.lerp(b, t));
+            if distance < closest.0 {
+                closest = (distance, travelled + segment * t);
+            }
+            travelled += segment;
+        }
+        closest.1
+    }
+}
+
+/// Pose a kinematic body was spawned in. Floors store absolute vertices, so they are drawn
+/// displaced from this pose.
+#[derive(Debug, Clone, Copy)]
+pub struct RestPose(pub Isometry2<f32>);
+
+impl RestPose {
+    /// Transform from the rest pose to where the body currently is.
+    pub fn displacement(&self, current: &Isometry2<f32>) -> Isometry2<f32> {
+        current * self.0.inverse()
+    }
+}
+
+/// Kinematic body that follows a `Track`, starting at the point closest to `pivot`.
+pub struct Platform {
+    pub track: Track,
+    /// point the platform rotates around, in its rest pose
+    pub pivot: Vec2,
+    pub start: f32,
+    pub time: f32,
+    pub paused: bool,
+}
+
+impl Platform {
+    pub fn new(track: Track, pivot: Vec2) -> Self {
+        let start = track.closest_distance(pivot);
+        Self {
+            track,
+            pivot,
+            start,
+            time: 0.0,
+            paused: false,
+        }
+    }
+
+    pub fn pose(&self, rest: &RestPose) -> Isometry2<f32> {
+        let offset = self
+            .track
+            .point_at(self.start + self.track.speed * self.time)
+            - self.track.point_at(self.start);
+        let rotation = UnitComplex::new(self.track.angular_speed * self.time);
+        let pivot = self.pivot + offset;
+        Translation2::new(pivot.x, pivot.y)
+            * rotation
+            * Translation2::new(-self.pivot.x, -self.pivot.y)
+            * rest.0
+    }
+}
+
+/// Makes the body of `entity` kinematic, remembering the pose it was spawned in.
+pub fn make_kinematic(world: &mut World, entity: Entity) -> RestPose {
+    if let Ok(rest) = world.entities.get::<&RestPose>(entity) {
+        return *rest;
+    }
+    let handle = *world.entities.get::<&RigidBodyHandle>(entity).unwrap();
+    let body = world.physics_world.get_body_mut(handle).unwrap();
+    body.set_body_type(RigidBodyType::KinematicVelocityBased, true);
+    let rest = RestPose(*body.position());
+    world.entities.insert_one(entity, rest).unwrap();
+    rest
+}
+
+/// Sets the velocity of a kinematic body so it reaches `target` after `dt`.
+pub fn drive_kinematic(body: &mut RigidBody, target: &Isometry2<f32>, dt: f32) {
+    if dt <= 0.0 {
+        return;
+    }
+    let current = *body.position();
+    // rapier integrates velocity around the center of mass, so aim that at the target
+    let com = *body.center_of_mass();
+    let target_com = target * current.inverse_transform_point(&com);
+    let angle = current.rotation.angle_to(&target.rotation);
+    body.set_linvel((target_com - com) / dt, true);
+    body.set_angvel(angle / dt, true);
+}
+
+/// Makes the body of `entity` kinematic and moves it along `track`.
+pub fn attach_platform(world: &mut World, entity: Entity, track: Track, pivot: Vec2) {
+    make_kinematic(world, entity);
+    world
+        .entities
+        .insert_one(entity, Platform::new(track, pivot))
+        .unwrap();
+}
+
+pub fn update_platforms(world: &mut World, dt: f32) {
+    for (_, (platform, rest, handle)) in world
+        .entities
+        .query_mut::<(&mut Platform, &RestPose, &RigidBodyHandle)>()
+    {
+        if !platform.paused {
+            platform.time += dt;
+        }
+        let target = platform.pose(rest);
+        let body = world.physics_world.get_body_mut(*handle).unwrap();
+        drive_kinematic(body, &target, dt);
+    }
+}
+
+/// Centroid of the vertices, used as the pivot of rotating floors.
+pub fn centroid(vertices: &[Vec2]) -> Vec2 {
+    vertices.iter().sum::<Vec2>() / vertices.len() as f32
+}
@@ -1,3 +1,5 @@
+use super::World;
+use super::floor::Material;
 use super::frame::{ContinuousFrame, Transition, Tween};
 use super::level::LevelId;
 use super::life_state::LifeState;
@@ -5,6 +7,7 @@ use super::physics_world::PhysicsWorld;
 use super::thing::ThingId;
 use crate::consts::*;
 use egui_macroquad::egui::epaint::ahash::HashSet;
+use hecs::Entity;
 use macroquad::prelude::*;
 use rapier2d::prelude::*;
 
@@ -20,6 +23,8 @@ pub struct Polly {
     pub feet_sensor_handles: [ColliderHandle; 3],
     pub feet_grounded: [bool; 3],
     pub feet_frame: ContinuousFrame,
+    /// seconds left falling through one-way platforms
+    pub drop_through: f32,
 }
 
 impl Polly {
@@ -43,14 +48,19 @@ impl Polly {
                 .build(),
             ColliderBuilder::capsule_x(0.05, 0.05)
                 .friction(PLAYER_FRICTION)
-                .active_events(ActiveEvents::CONTACT_FORCE_EVENTS)
+                .active_events(ActiveEvents::COLLISION_EVENTS | ActiveEvents::CONTACT_FORCE_EVENTS)
+                .contact_force_event_threshold(PLAYER_IMPACT_FORCE)
                 .friction_combine_rule(CoefficientCombineRule::Max)
                 .build(),
         );
 
         let mut make_foot = |builder: ColliderBuilder, offset: Vec2| {
             physics_world.collider_set.insert_with_parent(
-                builder.position(offset.into()).sensor(true).build(),
+                builder
+                    .position(offset.into())
+                    .sensor(true)
+                    .active_events(ActiveEvents::COLLISION_EVENTS)
+                    .build(),
                 body_handle,
                 &mut physics_world.rigid_body_set,
             )
@@ -71,6 +81,7 @@ impl Polly {
             feet_sensor_handles,
             feet_grounded,
             feet_frame,
+            drop_through: 0.0,
         }
     }
     pub fn despawn(&self, physics_world: &mut PhysicsWorld) {
@@ -104,11 +115,12 @@ impl Rolly {
                 .build(),
             ColliderBuilder::ball(0.075)
                 .friction(PLAYER_ROLL_FRICTION)
-                .active_events(ActiveEvents::CONTACT_FORCE_EVENTS)
+                .active_events(ActiveEvents::COLLISION_EVENTS | ActiveEvents::CONTACT_FORCE_EVENTS)
+                .contact_force_event_threshold(PLAYER_IMPACT_FORCE)
                 .friction_combine_rule(CoefficientCombineRule::Max)
                 .collision_groups(InteractionGroups::new(
                     COLLISION_LAYER_PLAYER.into(),
-                    COLLISION_LAYER_ENVIRONMENT.into(),
+                    (COLLISION_LAYER_ENVIRONMENT | COLLISION_LAYER_PROP).into(),
                 ))
                 .build(),
         );
@@ -121,6 +133,7 @@ impl Rolly {
         physics_world.remove_body(self.body_handle);
     }
 }
+/// Which form the player is in, with the physics handles of that form.
 #[derive(Debug, Clone, PartialEq)]
 pub enum Body {
     Polly(Polly),
@@ -134,6 +147,12 @@ impl Body {
             Body::Rolly(rolly) => rolly.body_handle,
         }
     }
+    pub fn base_friction(&self) -> f32 {
+        match self {
+            Body::Polly(_) => PLAYER_FRICTION,
+            Body::Rolly(_) => PLAYER_ROLL_FRICTION,
+        }
+    }
     pub fn any_collider_handle(&self) -> ColliderHandle {
         match self {
             Body::Polly(polly) => polly.collider_handle,
@@ -181,46 +200,30 @@ impl Body {
     }
 }
 
+/// Player-only state that isn't physics: where it faces, its animations and what it stands on.
 pub struct Player {
     pub direction: Direction,
-    pub body: Body,
     pub rolly_polly_transition: Transition,
     pub eye_x: Tween,
-    pub life_state: LifeState,
+    /// material the player is currently touching
+    pub ground: Option<Material>,
+}
+
+/// Respawns the player has reached, and the one it comes back to.
+#[derive(Debug, Clone)]
+pub struct RespawnProgress {
     respawn: (LevelId, ThingId),
     all_respawns: HashSet<(LevelId, ThingId)>,
 }
-impl Player {
-    pub fn spawn(physics_world: &mut PhysicsWorld, level: LevelId) -> Self {
-        let body = Body::Rolly(Rolly::spawn(
-            physics_world,
-            vec2(0.0, 0.0),
-            0.0,
-            vec2(0.0, 0.0),
-            0.0,
-        ));
 
-        let direction = Direction::Right;
-        let rolly_polly_transition = Transition::Start;
-        let eye_x = Tween::new(1.0, 0.05);
-
-        let life_state = LifeState::Dead(Transition::End);
+impl RespawnProgress {
+    pub fn new(level: LevelId) -> Self {
         let respawn = (level, ThingId(0));
-        let all_respawns = HashSet::from_iter([respawn]);
-
         Self {
-            direction,
-            body,
-            rolly_polly_transition,
-            eye_x,
-            life_state,
             respawn,
-            all_respawns,
+            all_respawns: HashSet::from_iter([respawn]),
         }
     }
-    pub fn alive(&self) -> bool {
-        matches!(self.life_state, LifeState::Alive(Transition::End))
-    }
     pub fn respawn(&self) -> (LevelId, ThingId) {
         self.respawn
     }
@@ -231,20 +234,61 @@ impl Player {
         self.respawn = respawn;
         self.all_respawns.insert(respawn);
     }
-    /// reset everything except life_state, respawn, and any physics_world state
-    pub fn reset(&mut self, physics_world: &mut PhysicsWorld) {
-        self.body.despawn(physics_world);
+}
 
-        self.body = Body::Rolly(Rolly::spawn(
-            physics_world,
+/// Dynamic body that mushrooms launch and flytraps spin. Bodies with a `Body` only get spun
+/// while rolly, and bodies with a `LifeState` only while alive.
+pub struct Launchable;
+
+impl Player {
+    fn new() -> Self {
+        Self {
+            direction: Direction::Right,
+            rolly_polly_transition: Transition::Start,
+            eye_x: Tween::new(1.0, 0.05),
+            ground: None,
+        }
+    }
+    /// Spawns the player entity as rolly at the origin, dead until it first respawns.
+    pub fn spawn(world: &mut World, level: LevelId) -> Entity {
+        let body = Body::Rolly(Rolly::spawn(
+            &mut world.physics_world,
             vec2(0.0, 0.0),
             0.0,
             vec2(0.0, 0.0),
             0.0,
         ));
-
-        self.direction = Direction::Right;
-        self.rolly_polly_transition = Transition::Start;
-        self.eye_x = Tween::new(1.0, 0.05);
+        let handle = body.any_body_handle();
+        let entity = world.entities.spawn((
+            Player::new(),
+            handle,
+            body,
+            LifeState::Dead(Transition::End),
+            RespawnProgress::new(level),
+            Launchable,
+        ));
+        world.physics_world.get_body_mut(handle).unwrap().user_data = entity.to_bits().get().into();
+        entity
+    }
+    /// Swaps the form of the player, keeping its rigid body handle in sync.
+    pub fn set_body(world: &mut World, body: Body) {
+        let handle = body.any_body_handle();
+        world.physics_world.get_body_mut(handle).unwrap().user_data =
+            world.player.to_bits().get().into();
+        world.entities.insert(world.player, (handle, body)).unwrap();
+    }
+    /// reset everything except life_state, respawn, and any physics_world state
+    pub fn reset(world: &mut World) {
+        let old_body = Body::clone(&world.form());
+        old_body.despawn(&mut world.physics_world);
+        let body = Body::Rolly(Rolly::spawn(
+            &mut world.physics_world,
+            vec2(0.0, 0.0),
+            0.0,
+            vec2(0.0, 0.0),
+            0.0,
+        ));
+        Player::set_body(world, body);
+        *world.player_mut() = Player::new();
     }
 }
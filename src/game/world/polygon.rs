@@ -188,3 +188,38 @@ pub fn add_rect_padding(rect: Rect, padding: f32) -> Rect {
         rect.h + padding * 2.0,
     )
 }
+
+/// Drops vertices that are closer than `tolerance` to the line through their neighbours
+/// (Ramer-Douglas-Peucker), keeping the polygon closed.
+pub fn simplify_polygon(vertices: &[Vec2], tolerance: f32) -> Vec<Vec2> {
+    fn simplify_line(line: &[Vec2], tolerance: f32, simplified: &mut Vec<Vec2>) {
+        let (first, last) = (line[0], line[line.len() - 1]);
+        let farthest = line[1..line.len() - 1]
+            .iter()
+            .enumerate()
+            .map(|(i, v)| {
+                let distance = if first == last {
+                    v.distance(first)
+                } else {
+                    (*v - first).perp_dot((last - first).normalize()).abs()
+                };
+                (i + 1, distance)
+            })
+            .max_by(|(_, a), (_, b)| a.total_cmp(b));
+        match farthest {
+            Some((i, distance)) if distance > tolerance => {
+                simplify_line(&line[..=i], tolerance, simplified);
+                simplify_line(&line[i..], tolerance, simplified);
+            }
+            _ => simplified.push(first),
+        }
+    }
+    if vertices.len() <= 3 {
+        return vertices.to_vec();
+    }
+    let mut line = vertices.to_vec();
+    line.push(vertices[0]);
+    let mut simplified = Vec::new();
+    simplify_line(&line, tolerance, &mut simplified);
+    simplified
+}
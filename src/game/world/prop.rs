@@ -0,0 +1,69 @@
+use macroquad::prelude::*;
+use rapier2d::prelude::*;
+
+use crate::consts::*;
+use crate::game::assets::Assets;
+
+use super::World;
+use super::collider;
+use super::floor::Material;
+use super::player::Launchable;
+use super::thing::{EntityBuilder, ThingDraw};
+
+/// Loose object that falls, gets pushed around, and floats or sinks in water.
+pub struct Prop {
+    /// relative to water, so below 1 floats
+    pub density: f32,
+    /// covers the collider, for loading lazy colliders around the prop
+    pub radius: f32,
+    /// where the prop goes back to if it falls out of its level
+    pub spawn: Isometry<Real>,
+}
+
+/// Spawns a dynamic prop with the collider of `texture`.
+pub fn prop_thing(
+    assets: &Assets,
+    world: &mut World,
+    pos: Vec2,
+    rotation: f32,
+    texture: &str,
+    material: Material,
+    density: f32,
+) -> EntityBuilder {
+    let body = RigidBodyBuilder::dynamic()
+        .translation(pos.into())
+        .rotation(rotation)
+        .ccd_enabled(CCD_ENABLED);
+    let body_handle = world.physics_world.add_body(body.build());
+
+    let (rect, collider) = collider::load_collider(assets, texture)
+        .unwrap_or_else(|| panic!("prop {} has no collider", texture));
+    let collider = collider
+        .density(density)
+        .friction(PLATFORM_FRICTION)
+        .friction_combine_rule(CoefficientCombineRule::Max)
+        .restitution(material.restitution())
+        .active_events(ActiveEvents::COLLISION_EVENTS)
+        .collision_groups(InteractionGroups::new(
+            COLLISION_LAYER_PROP.into(),
+            (COLLISION_LAYER_PLAYER | COLLISION_LAYER_ENVIRONMENT | COLLISION_LAYER_PROP).into(),
+        ));
+    let collider_handle = world
+        .physics_world
+        .add_collider(collider.build(), body_handle);
+
+    EntityBuilder::new()
+        .add(body_handle)
+        .add(collider_handle)
+        .add(ThingDraw {
+            texture: texture.to_owned(),
+            ..Default::default()
+        })
+        .add(material)
+        .add(Prop {
+            density,
+            radius: rect.size().length() / 2.0,
+            spawn: Isometry::new(pos.into(), rotation),
+        })
+        .add(Launchable)
+}
@@ -0,0 +1,207 @@
+use std::collections::HashMap;
+
+use macroquad::prelude::*;
+
+use crate::game::assets::Assets;
+use crate::game::config::GameConfig;
+use crate::game::ui::settings::Settings;
+
+use super::World;
+
+/// how much of the latest frame goes into the shown timings
+const SCHEDULE_TIMING_SMOOTHING: f64 = 0.05;
+
+/// Everything a system gets to look at.
+pub struct SystemContext<'a> {
+    pub assets: &'a Assets,
+    pub settings: &'a Settings,
+    pub config: &'a GameConfig,
+    pub world: &'a mut World,
+}
+
+/// When a system runs, checked right before running it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RunIf {
+    Always,
+    Alive,
+    Polly,
+    /// polly and alive
+    PollyAlive,
+    Cheat,
+}
+
+impl RunIf {
+    fn check(self, ctx: &SystemContext) -> bool {
+        let world = &ctx.world;
+        match self {
+            Self::Always => true,
+            Self::Alive => world.player_alive(),
+            Self::Polly => world.form().is_polly(),
+            Self::PollyAlive => world.form().is_polly() && world.player_alive(),
+            Self::Cheat => ctx.config.cheat,
+        }
+    }
+}
+
+pub struct System {
+    pub name: &'static str,
+    pub run: fn(&mut SystemContext),
+    pub run_if: RunIf,
+    /// names of systems that have to run first
+    pub after: &'static [&'static str],
+}
+
+impl System {
+    pub fn new(name: &'static str, run: fn(&mut SystemContext)) -> Self {
+        Self {
+            name,
+            run,
+            run_if: RunIf::Always,
+            after: &[],
+        }
+    }
+    pub fn run_if(self, run_if: RunIf) -> Self {
+        Self { run_if, ..self }
+    }
+    pub fn after(self, after: &'static [&'static str]) -> Self {
+        Self { after, ..self }
+    }
+}
+
+/// Systems in the order they run, with how long each took.
+#[derive(Default)]
+pub struct Schedule {
+    systems: Vec<System>,
+    /// smoothed seconds per system, in the same order as `systems`
+    timings: Vec<f64>,
+}
+
+impl Schedule {
+    /// Orders `systems` so every system runs after the ones it names, keeping the given order
+    /// where there are no constraints. Panics on unknown names and cycles.
+    pub fn new(mut systems: Vec<System>) -> Self {
+        let index: HashMap<&str, usize> = systems
+            .iter()
+            .enumerate()
+            .map(|(i, system)| (system.name, i))
+            .collect();
+        assert_eq!(index.len(), systems.len(), "duplicate system names");
+        let dependencies: Vec<Vec<usize>> = systems
+            .iter()
+            .map(|system| {
+                system
+                    .after
+                    .iter()
+                    .map(|name| {
+                        *index.get(name).unwrap_or_else(|| {
+                            panic!("{} runs after unknown system {}", system.name, name)
+                        })
+                    })
+                    .collect()
+            })
+            .collect();
+
+        let mut order = Vec::with_capacity(systems.len());
+        let mut placed = vec![false; systems.len()];
+        while order.len() < systems.len() {
+            let next = (0..systems.len())
+                .find(|&i| !placed[i] && dependencies[i].iter().all(|&d| placed[d]))
+                .unwrap_or_else(|| panic!("systems depend on each other in a cycle"));
+            placed[next] = true;
+            order.push(next);
+        }
+
+        let mut slots = systems.drain(..).map(Some).collect::<Vec<_>>();
+        let systems = order
+            .into_iter()
+            .map(|i| slots[i].take().unwrap())
+            .collect::<Vec<_>>();
+        Self {
+            timings: vec![0.0; systems.len()],
+            systems,
+        }
+    }
+
+    pub fn run(&mut self, ctx: &mut SystemContext) {
+        for (system, timing) in self.systems.iter().zip(self.timings.iter_mut()) {
+            if !system.run_if.check(ctx) {
+                *timing *= 1.0 - SCHEDULE_TIMING_SMOOTHING;
+                continue;
+            }
+            let start = get_time();
+            (system.run)(ctx);
+            let elapsed = get_time() - start;
+            *timing += (elapsed - *timing) * SCHEDULE_TIMING_SMOOTHING;
+        }
+    }
+
+    /// (name, smoothed seconds) of every system in run order.
+    pub fn timings(&self) -> impl Iterator<Item = (&'static str, f64)> + '_ {
+        self.systems
+            .iter()
+            .zip(self.timings.iter())
+            .map(|(system, timing)| (system.name, *timing))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn noop(_: &mut SystemContext) {}
+
+    fn order(schedule: &Schedule) -> Vec<&'static str> {
+        schedule.timings().map(|(name, _)| name).collect()
+    }
+
+    #[test]
+    fn runs_after_dependencies() {
+        let schedule = Schedule::new(vec![
+            System::new("draw", noop).after(&["physics", "input"]),
+            System::new("physics", noop).after(&["input"]),
+            System::new("input", noop),
+        ]);
+        assert_eq!(order(&schedule), vec!["input", "physics", "draw"]);
+    }
+
+    #[test]
+    fn keeps_given_order_without_constraints() {
+        let schedule = Schedule::new(vec![
+            System::new("a", noop),
+            System::new("b", noop),
+            System::new("c", noop).after(&["d"]),
+            System::new("d", noop),
+        ]);
+        assert_eq!(order(&schedule), vec!["a", "b", "d", "c"]);
+    }
+
+    #[test]
+    fn game_schedule_is_valid() {
+        let schedule = crate::game::world::update::schedule();
+        let order = order(&schedule);
+        let position = |name| order.iter().position(|n| *n == name).unwrap();
+        assert!(position("physics") < position("current level"));
+        assert!(position("current level") < position("fall"));
+    }
+
+    #[test]
+    #[should_panic(expected = "cycle")]
+    fn panics_on_cycle() {
+        Schedule::new(vec![
+            System::new("a", noop).after(&["b"]),
+            System::new("b", noop).after(&["a"]),
+        ]);
+    }
+
+    #[test]
+    #[should_panic(expected = "a runs after unknown system b")]
+    fn panics_on_unknown_dependency() {
+        Schedule::new(vec![System::new("a", noop).after(&["b"])]);
+    }
+
+    #[test]
+    #[should_panic(expected = "duplicate system names")]
+    fn panics_on_duplicate_names() {
+        Schedule::new(vec![System::new("a", noop), System::new("a", noop)]);
+    }
+}
@@ -0,0 +1,224 @@
+use std::collections::{HashMap, HashSet};
+
+use hecs::{Entity, Or};
+use macroquad::prelude::*;
+use nalgebra::{Isometry2, Translation2};
+use rapier2d::prelude::*;
+
+use crate::consts::*;
+
+use super::World;
+use super::draw::pixel_to_meter;
+use super::light::LightGroup;
+use super::platform::{Platform, RestPose, drive_kinematic, make_kinematic};
+use super::svg::{SvgItem, SvgShape};
+
+/// Named on/off values that triggers emit and receivers respond to.
+#[derive(Debug, Clone, Default)]
+pub struct Signals(pub HashMap<String, bool>);
+
+impl Signals {
+    pub fn get(&self, name: &str) -> bool {
+        self.0.get(name).copied().unwrap_or(false)
+    }
+    pub fn set(&mut self, name: &str, value: bool) {
+        self.0.insert(name.to_owned(), value);
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum TriggerArea {
+    Rect(Rect),
+    Circle(Vec2, f32),
+}
+
+impl TriggerArea {
+    pub fn from_shape(shape: &SvgShape, pos: Vec2) -> Self {
+        match shape {
+            SvgShape::Rect(rect) => Self::Rect(Rect::new(
+                rect.pos.x + pos.x - rect.dims.x / 2.0,
+                rect.pos.y + pos.y - rect.dims.y / 2.0,
+                rect.dims.x,
+                rect.dims.y,
+            )),
+            SvgShape::Circle(circle) => Self::Circle(circle.pos + pos, circle.r),
+            SvgShape::Path(_) => panic!("trigger areas must be rects or circles"),
+        }
+    }
+    pub fn contains(&self, point: Vec2) -> bool {
+        match self {
+            Self::Rect(rect) => rect.contains(point),
+            Self::Circle(center, r) => center.distance(point) < *r,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum TriggerKind {
+    /// active while the player touches the body of this entity
+    Plate,
+    /// active while the player is inside the area
+    Zone(TriggerArea),
+    /// flips every time rolly enters the area
+    Lever(TriggerArea),
+}
+
+/// Emits `signal`, authored with `data-emit="name"`. Plates are regular things or floors,
+/// zones and levers are invisible areas with `data-trigger="zone"` or `data-trigger="lever"`.
+/// `data-latch` keeps the signal on once it has been triggered.
+#[derive(Debug, Clone)]
+pub struct Trigger {
+    pub signal: String,
+    pub kind: TriggerKind,
+    pub latch: bool,
+    pub active: bool,
+    touching: bool,
+}
+
+impl Trigger {
+    pub fn from_svg_item(item: &SvgItem, pos: Vec2, signals: &Signals) -> Option<Self> {
+        let signal = item.data("emit")?.to_owned();
+        let kind = match item.data("trigger") {
+            None | Some("plate") => TriggerKind::Plate,
+            Some("zone") => TriggerKind::Zone(TriggerArea::from_shape(&item.shape, pos)),
+            Some("lever") => TriggerKind::Lever(TriggerArea::from_shape(&item.shape, pos)),
+            Some(kind) => panic!("unknown trigger kind: {}", kind),
+        };
+        let latch = item.data("latch").is_some();
+        // levers and latches remember their state, so a reloaded level matches the save
+        let active = match kind {
+            TriggerKind::Lever(_) => signals.get(&signal),
+            _ => latch && signals.get(&signal),
+        };
+        Some(Self {
+            signal,
+            kind,
+            latch,
+            active,
+            touching: false,
+        })
+    }
+    /// Invisible triggers don't spawn a thing of their own.
+    pub fn is_area(&self) -> bool {
+        !matches!(self.kind, TriggerKind::Plate)
+    }
+}
+
+/// Responds to `signal`, authored with `data-signal="name"` and optionally `data-invert`.
+/// Platforms stop while the signal is off, lights turn off, and anything else is a gate
+/// that slides open by `data-open-x`/`data-open-y` (px) while the signal is on.
+#[derive(Debug, Clone)]
+pub struct Receiver {
+    pub signal: String,
+    pub invert: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct Gate {
+    pub offset: Vec2,
+    /// 0 is closed, 1 is open
+    pub open: f32,
+}
+
+impl Gate {
+    pub fn pose(&self, rest: &RestPose) -> Isometry2<f32> {
+        let offset = self.offset * simple_easing::sine_in_out(self.open);
+        Translation2::new(offset.x, offset.y) * rest.0
+    }
+}
+
+/// Attaches a receiver to `entity`, turning it into a gate if nothing else can respond.
+pub fn attach_receiver(world: &mut World, entity: Entity, item: &SvgItem, default_offset: Vec2) {
+    let Some(signal) = item.data("signal") else {
+        return;
+    };
+    let receiver = Receiver {
+        signal: signal.to_owned(),
+        invert: item.data("invert").is_some(),
+    };
+    let responds = world
+        .entities
+        .satisfies::<Or<&Platform, &LightGroup>>(entity)
+        .unwrap();
+    if !responds {
+        let rest = make_kinematic(world, entity);
+        let offset = match (item.data_f32("open-x"), item.data_f32("open-y")) {
+            (None, None) => default_offset,
+            (x, y) => pixel_to_meter(vec2(x.unwrap_or(0.0), y.unwrap_or(0.0))),
+        };
+        let open = if world.signals.get(&receiver.signal) != receiver.invert {
+            1.0
+        } else {
+            0.0
+        };
+        let gate = Gate { offset, open };
+        let handle = *world.entities.get::<&RigidBodyHandle>(entity).unwrap();
+        let body = world.physics_world.get_body_mut(handle).unwrap();
+        body.set_position(gate.pose(&rest), true);
+        world.entities.insert_one(entity, gate).unwrap();
+    }
+    world.entities.insert_one(entity, receiver).unwrap();
+}
+
+pub fn update_triggers(world: &mut World) {
+    let player_handle = world.player_body_handle();
+    let player_body = world.physics_world.get_body(player_handle).unwrap();
+    let player_pos = Vec2::from(player_body.position().translation);
+    let touched = world
+        .contacts
+        .touching(&world.physics_world, None)
+        .collect::<HashSet<_>>();
+    let rolly = world.form().is_rolly();
+    for (entity, trigger) in world.entities.query_mut::<&mut Trigger>() {
+        let touching = match trigger.kind {
+            TriggerKind::Plate => touched.contains(&entity),
+            TriggerKind::Zone(area) => area.contains(player_pos),
+            TriggerKind::Lever(area) => rolly && area.contains(player_pos),
+        };
+        match trigger.kind {
+            TriggerKind::Lever(_) => {
+                if touching && !trigger.touching {
+                    trigger.active = !trigger.active;
+                }
+            }
+            _ => trigger.active = touching || (trigger.latch && trigger.active),
+        }
+        trigger.touching = touching;
+    }
+
+    // a signal is on if any loaded trigger emitting it is active, signals without loaded
+    // triggers keep their last value
+    let mut values: HashMap<String, bool> = HashMap::new();
+    for (_, trigger) in world.entities.query_mut::<&Trigger>() {
+        *values.entry(trigger.signal.clone()).or_default() |= trigger.active;
+    }
+    for (signal, value) in values {
+        world.signals.set(&signal, value);
+    }
+}
+
+pub fn update_receivers(world: &mut World, dt: f32) {
+    for (_, (receiver, gate, platform, light_group, rest, handle)) in world.entities.query_mut::<(
+        &Receiver,
+        Option<&mut Gate>,
+        Option<&mut Platform>,
+        Option<&mut LightGroup>,
+        Option<&RestPose>,
+        Option<&RigidBodyHandle>,
+    )>() {
+        let on = world.signals.get(&receiver.signal) != receiver.invert;
+        if let Some(platform) = platform {
+            platform.paused = !on;
+        }
+        if let Some(light_group) = light_group {
+            light_group.enabled = on;
+        }
+        if let (Some(gate), Some(rest), Some(handle)) = (gate, rest, handle) {
+            let target = if on { 1.0 } else { 0.0 };
+            let step = GATE_OPEN_SPEED * dt;
+            gate.open += (target - gate.open).clamp(-step, step);
+            let body = world.physics_world.get_body_mut(*handle).unwrap();
+            drive_kinematic(body, &gate.pose(rest), dt);
+        }
+    }
+}
@@ -0,0 +1,114 @@
+use std::collections::HashMap;
+
+use macroquad::prelude::*;
+use nalgebra::{Isometry2, Translation2};
+use rapier2d::prelude::*;
+
+use super::World;
+use super::level::LevelId;
+use super::platform::Platform;
+use super::signal::Gate;
+use super::thing::{Flytrap, Respawn, ThingDraw, ThingId, ThingPart};
+
+/// State of one entity that changes while its level is loaded.
+#[derive(Debug, Clone, Default)]
+struct EntitySnapshot {
+    /// pose relative to the level, velocity and angular velocity of a body that can move
+    body: Option<(Isometry2<f32>, Vector<f32>, f32)>,
+    platform_time: Option<f32>,
+    gate_open: Option<f32>,
+    /// teeth position and speed
+    flytrap: Option<(f32, f32)>,
+    respawn: Option<(Respawn, ThingDraw)>,
+}
+
+/// Everything that moved or changed in a level when it unloaded, so loading it again picks up
+/// where it left off instead of starting from the svg.
+#[derive(Debug, Clone, Default)]
+pub struct LevelSnapshot(HashMap<(ThingId, ThingPart), EntitySnapshot>);
+
+/// Snapshots the entities of `level`, which is at `pos`, before it unloads.
+pub fn take_snapshot(world: &mut World, level: LevelId, pos: Vec2) {
+    let to_level = Translation2::new(-pos.x, -pos.y);
+    let mut snapshot = LevelSnapshot::default();
+    for (_, (entity_level, thing_id, part, body, platform, gate, flytrap, respawn)) in
+        world.entities.query_mut::<(
+            &LevelId,
+            &ThingId,
+            &ThingPart,
+            Option<&RigidBodyHandle>,
+            Option<&Platform>,
+            Option<&Gate>,
+            Option<&Flytrap>,
+            Option<(&Respawn, &ThingDraw)>,
+        )>()
+    {
+        if *entity_level != level {
+            continue;
+        }
+        let body = body
+            .map(|handle| world.physics_world.get_body(*handle).unwrap())
+            .filter(|body| !body.is_fixed())
+            .map(|body| (to_level * body.position(), *body.linvel(), body.angvel()));
+        snapshot.0.insert(
+            (*thing_id, *part),
+            EntitySnapshot {
+                body,
+                platform_time: platform.map(|platform| platform.time),
+                gate_open: gate.map(|gate| gate.open),
+                flytrap: flytrap.map(|flytrap| (flytrap.teeth_x, flytrap.teeth_speed)),
+                respawn: respawn.map(|(respawn, draw)| (respawn.clone(), draw.clone())),
+            },
+        );
+    }
+    world.snapshots.insert(level, snapshot);
+}
+
+/// Puts the entities of `level`, just loaded at `pos`, back how they were when it unloaded.
+pub fn restore_snapshot(world: &mut World, level: LevelId, pos: Vec2) {
+    let Some(snapshot) = world.snapshots.remove(&level) else {
+        return;
+    };
+    let from_level = Translation2::new(pos.x, pos.y);
+    for (_, (entity_level, thing_id, part, body, platform, gate, flytrap, respawn)) in
+        world.entities.query_mut::<(
+            &LevelId,
+            &ThingId,
+            &ThingPart,
+            Option<&RigidBodyHandle>,
+            Option<&mut Platform>,
+            Option<&mut Gate>,
+            Option<&mut Flytrap>,
+            Option<(&mut Respawn, &mut ThingDraw)>,
+        )>()
+    {
+        if *entity_level != level {
+            continue;
+        }
+        let Some(saved) = snapshot.0.get(&(*thing_id, *part)) else {
+            continue;
+        };
+        if let (Some(handle), Some((position, linvel, angvel))) = (body, saved.body) {
+            let body = world.physics_world.get_body_mut(*handle).unwrap();
+            body.set_position(from_level * position, true);
+            body.set_linvel(linvel, true);
+            body.set_angvel(angvel, true);
+        }
+        if let (Some(platform), Some(time)) = (platform, saved.platform_time) {
+            platform.time = time;
+        }
+        if let (Some(gate), Some(open)) = (gate, saved.gate_open) {
+            gate.open = open;
+        }
+        if let (Some(flytrap), Some((teeth_x, teeth_speed))) = (flytrap, saved.flytrap) {
+            flytrap.teeth_x = teeth_x;
+            flytrap.teeth_speed = teeth_speed;
+        }
+        if let (Some((respawn, draw)), Some((saved_respawn, saved_draw))) =
+            (respawn, &saved.respawn)
+        {
+            *respawn = saved_respawn.clone();
+            *draw = saved_draw.clone();
+        }
+    }
+}
@@ -0,0 +1,162 @@
+use std::collections::HashSet;
+
+use nanoserde::{DeJson, SerJson};
+
+use crate::consts::SPLITS_KEY;
+
+use super::level::LevelId;
+
+/// Best times kept between runs.
+#[derive(Debug, Clone, Default, SerJson, DeJson)]
+pub struct SplitRecords {
+    /// (level, run time) of every split of the personal best run
+    #[nserde(default)]
+    pub pb: Vec<(usize, f64)>,
+    /// (from, to, segment) of the fastest segment over each link between levels, so routes
+    /// entering a level from different levels keep their own golds
+    #[nserde(default)]
+    pub link_golds: Vec<(usize, usize, f64)>,
+}
+
+impl SplitRecords {
+    pub fn load() -> Self {
+        quad_storage::STORAGE
+            .lock()
+            .unwrap()
+            .get(SPLITS_KEY)
+            .and_then(|json| SplitRecords::deserialize_json(&json).ok())
+            .unwrap_or_default()
+    }
+    pub fn store(&self) {
+        quad_storage::STORAGE
+            .lock()
+            .unwrap()
+            .set(SPLITS_KEY, &self.serialize_json());
+    }
+
+    /// Fastest segment from `from` into `to`.
+    pub fn gold(&self, from: LevelId, to: LevelId) -> Option<f64> {
+        self.link_golds
+            .iter()
+            .find(|(gold_from, gold_to, _)| *gold_from == from.0 && *gold_to == to.0)
+            .map(|(_, _, segment)| *segment)
+    }
+
+    fn set_gold(&mut self, from: LevelId, to: LevelId, segment: f64) {
+        self.link_golds
+            .retain(|(gold_from, gold_to, _)| *gold_from != from.0 || *gold_to != to.0);
+        self.link_golds.push((from.0, to.0, segment));
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Split {
+    pub level: LevelId,
+    pub time: f64,
+    pub segment: f64,
+    /// difference to the same split of the personal best, if it got there
+    pub delta: Option<f64>,
+    pub gold: bool,
+}
+
+/// Run timer driven by simulation time, so it doesn't depend on the frame rate.
+#[derive(Debug, Clone)]
+pub struct Speedrun {
+    /// only runs from the very start update the records
+    pub ranked: bool,
+    pub started: bool,
+    pub time: f64,
+    pub splits: Vec<Split>,
+    pub records: SplitRecords,
+    visited: HashSet<LevelId>,
+    /// level the player was in last tick, where a split comes from
+    level: LevelId,
+}
+
+impl Speedrun {
+    pub fn new(ranked: bool) -> Self {
+        Self {
+            ranked,
+            started: false,
+            time: 0.0,
+            splits: Vec::new(),
+            records: SplitRecords::load(),
+            visited: HashSet::new(),
+            level: LevelId::first(),
+        }
+    }
+
+    /// Starts once the player is first alive, and splits on entering a level for the first time.
+    pub fn tick(&mut self, dt: f32, alive: bool, level: LevelId) {
+        if !self.started {
+            if !alive {
+                return;
+            }
+            self.started = true;
+            self.visited.insert(level);
+            self.level = level;
+        }
+        self.time += dt as f64;
+        if self.visited.insert(level) {
+            self.split(self.level, level);
+        }
+        self.level = level;
+    }
+
+    fn split(&mut self, from: LevelId, level: LevelId) {
+        let segment = self.time - self.splits.last().map_or(0.0, |split| split.time);
+        let best = self
+            .records
+            .pb
+            .iter()
+            .find(|(pb_level, _)| *pb_level == level.0)
+            .map(|(_, time)| *time);
+        let gold = self.records.gold(from, level);
+        self.splits.push(Split {
+            level,
+            time: self.time,
+            segment,
+            delta: best.map(|best| self.time - best),
+            gold: gold.is_none_or(|gold| segment < gold),
+        });
+        if self.ranked && gold.is_none_or(|gold| segment < gold) {
+            self.records.set_gold(from, level, segment);
+        }
+    }
+
+    /// Ends the run, keeping it as the personal best if it beat it and storing the records.
+    pub fn finish(&mut self) {
+        if !self.ranked || self.splits.is_empty() {
+            return;
+        }
+        // the personal best is whichever run got further, or got as far faster
+        let pb_len = self.records.pb.len();
+        let further = self.splits.len() > pb_len;
+        let faster = self.splits.len() == pb_len
+            && self
+                .records
+                .pb
+                .last()
+                .is_some_and(|(_, time)| self.splits.last().unwrap().time < *time);
+        if further || faster {
+            self.records.pb = self
+                .splits
+                .iter()
+                .map(|split| (split.level.0, split.time))
+                .collect();
+        }
+        self.records.store();
+    }
+}
+
+/// Formats seconds as `m:ss.cc`.
+pub fn format_time(time: f64) -> String {
+    let centis = (time.abs() * 100.0).round() as u64;
+    format!(
+        "{}{}:{:02}.{:02}",
+        if time < 0.0 { "-" } else { "" },
+        centis / 6000,
+        centis / 100 % 60,
+        centis % 100
+    )
+}
@@ -0,0 +1,164 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use macroquad::prelude::*;
+
+use crate::game::assets::{Assets, Tiles};
+
+use super::floor::{FloorShape, Material};
+use super::level::LevelId;
+use super::svg::{SvgItem, SvgShape, read_svg};
+
+#[cfg(target_arch = "wasm32")]
+use macroquad::experimental::coroutines::{Coroutine, start_coroutine};
+
+/// Everything about a level that only depends on its svg, relative to the level, so it can be
+/// made ahead of time and loading only has to fill in the world.
+pub struct PreparedLevel {
+    pub items: Vec<SvgItem>,
+    /// floor shapes by svg item index
+    pub floors: HashMap<usize, FloorShape>,
+}
+
+impl PreparedLevel {
+    /// Parses the svg, without preparing any floors yet.
+    fn parse(svg: &str) -> Self {
+        let (_, items) = read_svg(svg);
+        Self {
+            items,
+            floors: HashMap::new(),
+        }
+    }
+
+    /// Items that become floors if nothing else claims them.
+    fn floor_items(&self) -> Vec<(usize, Vec<Vec2>, Material)> {
+        self.items
+            .iter()
+            .filter(|item| item.data("track-id").is_none())
+            .filter_map(|item| match &item.shape {
+                SvgShape::Path(path) => Some((
+                    item.index,
+                    path.vertices.clone(),
+                    Material::try_from_hex_color(item.color)?,
+                )),
+                _ => None,
+            })
+            .collect()
+    }
+
+    pub fn new(tiles: &Tiles, svg: &str) -> Self {
+        let mut level = Self::parse(svg);
+        for (index, vertices, material) in level.floor_items() {
+            let shape = FloorShape::new(tiles, &vertices, material);
+            level.floors.insert(index, shape);
+        }
+        level
+    }
+
+    /// Like `new`, but gives the frame back after every floor.
+    #[cfg(target_arch = "wasm32")]
+    async fn new_async(tiles: &Tiles, svg: &str) -> Self {
+        let mut level = Self::parse(svg);
+        next_frame().await;
+        for (index, vertices, material) in level.floor_items() {
+            let shape = FloorShape::new(tiles, &vertices, material);
+            level.floors.insert(index, shape);
+            next_frame().await;
+        }
+        level
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+type Job = std::thread::JoinHandle<PreparedLevel>;
+#[cfg(target_arch = "wasm32")]
+type Job = Coroutine<PreparedLevel>;
+
+/// Prepares levels in the background before they are needed, on a thread or in a coroutine
+/// on wasm, and keeps them for when they load again.
+pub struct LevelStreamer {
+    tiles: Arc<Tiles>,
+    ready: HashMap<LevelId, Arc<PreparedLevel>>,
+    jobs: HashMap<LevelId, Job>,
+}
+
+impl LevelStreamer {
+    pub fn new(assets: &Assets) -> Self {
+        Self {
+            tiles: Arc::new(assets.tiles.clone()),
+            ready: assets
+                .level_cache
+                .iter()
+                .map(|(level, prepared)| (LevelId(*level), prepared.clone()))
+                .collect(),
+            jobs: HashMap::new(),
+        }
+    }
+
+    /// Starts preparing `level`, unless it already is.
+    pub fn request(&mut self, assets: &Assets, level: LevelId) {
+        if self.ready.contains_key(&level) || self.jobs.contains_key(&level) {
+            return;
+        }
+        let tiles = self.tiles.clone();
+        let svg = assets.levels[&level.0].1.clone();
+        #[cfg(not(target_arch = "wasm32"))]
+        let job = std::thread::spawn(move || PreparedLevel::new(&tiles, &svg));
+        #[cfg(target_arch = "wasm32")]
+        let job = start_coroutine(async move { PreparedLevel::new_async(&tiles, &svg).await });
+        self.jobs.insert(level, job);
+    }
+
+    /// Keeps the levels that finished preparing.
+    pub fn poll(&mut self) {
+        let finished = self
+            .jobs
+            .iter()
+            .filter(|(_, job)| job_finished(job))
+            .map(|(level, _)| *level)
+            .collect::<Vec<_>>();
+        for level in finished {
+            let job = self.jobs.remove(&level).unwrap();
+            if let Some(prepared) = finish_job(job) {
+                self.ready.insert(level, Arc::new(prepared));
+            }
+        }
+    }
+
+    /// `level` prepared, waiting for the background or preparing it right away if it isn't
+    /// there yet.
+    pub fn get(&mut self, assets: &Assets, level: LevelId) -> Arc<PreparedLevel> {
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(job) = self.jobs.remove(&level)
+            && let Some(prepared) = finish_job(job)
+        {
+            self.ready.insert(level, Arc::new(prepared));
+        }
+        if let Some(ready) = self.ready.get(&level) {
+            return ready.clone();
+        }
+        let svg = &assets.levels[&level.0].1;
+        let prepared = Arc::new(PreparedLevel::new(&self.tiles, svg));
+        self.ready.insert(level, prepared.clone());
+        prepared
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn job_finished(job: &Job) -> bool {
+    job.is_finished()
+}
+#[cfg(target_arch = "wasm32")]
+fn job_finished(job: &Job) -> bool {
+    job.is_done()
+}
+
+/// Waits for the job, `None` if it panicked or was stopped.
+#[cfg(not(target_arch = "wasm32"))]
+fn finish_job(job: Job) -> Option<PreparedLevel> {
+    job.join().ok()
+}
+#[cfg(target_arch = "wasm32")]
+fn finish_job(job: Job) -> Option<PreparedLevel> {
+    job.retrieve()
+}
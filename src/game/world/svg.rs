@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use std::f32::consts::PI;
 
 use macroquad::prelude::*;
@@ -20,6 +21,21 @@ pub enum SvgShape {
     Circle(CircleShape),
 }
 
+impl SvgShape {
+    /// Unrotated bounding box dimensions.
+    pub fn dims(&self) -> Vec2 {
+        match self {
+            SvgShape::Rect(rect) => rect.dims,
+            SvgShape::Circle(circle) => Vec2::splat(circle.r * 2.0),
+            SvgShape::Path(path) => {
+                let min = path.vertices.iter().copied().reduce(Vec2::min).unwrap();
+                let max = path.vertices.iter().copied().reduce(Vec2::max).unwrap();
+                max - min
+            }
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct RectShape {
     /// center pos
@@ -46,6 +62,20 @@ pub struct SvgItem {
     pub shape: SvgShape,
     pub color: u32,
     pub index: usize,
+    /// `data-*` attributes, keyed without the `data-` prefix
+    pub data: HashMap<String, String>,
+}
+
+impl SvgItem {
+    pub fn data(&self, key: &str) -> Option<&str> {
+        self.data.get(key).map(|s| s.as_str())
+    }
+    pub fn data_f32(&self, key: &str) -> Option<f32> {
+        self.data(key).map(|s| {
+            s.parse()
+                .unwrap_or_else(|_| panic!("data-{} attribute is not a number", key))
+        })
+    }
 }
 
 type StartElementEvent = (OwnedName, Vec<OwnedAttribute>, Namespace);
@@ -76,18 +106,17 @@ pub fn read_svg(svg: &str) -> (Vec2, Vec<SvgItem>) {
                 ref namespace,
             }) if name.local_name == "rect"
                 || name.local_name == "path"
-                || name.local_name == "circle" =>
+                || name.local_name == "circle"
+                || name.local_name == "line" =>
             {
-                let color = u32::from_str_radix(
-                    attributes
-                        .iter()
-                        .find(|a| a.name.local_name == "fill")
-                        .expect("no fill attribute")
-                        .value
-                        .trim_start_matches("#"),
-                    16,
-                )
-                .expect("fill attribute is not a hex color");
+                // lines and other markers only have a stroke
+                let color = ["fill", "stroke"]
+                    .into_iter()
+                    .filter_map(|attr| get_attr(attributes, attr))
+                    .find(|color| *color != "none")
+                    .expect("no fill or stroke attribute");
+                let color = u32::from_str_radix(color.trim_start_matches("#"), 16)
+                    .expect("fill attribute is not a hex color");
                 let shape = match name.local_name.as_str() {
                     "rect" => SvgShape::Rect(read_svg_rect(attributes)),
                     "path" => SvgShape::Path(read_svg_path(
@@ -95,12 +124,21 @@ pub fn read_svg(svg: &str) -> (Vec2, Vec<SvgItem>) {
                         (name.clone(), attributes.clone(), namespace.clone()),
                     )),
                     "circle" => SvgShape::Circle(read_svg_circle(attributes)),
+                    "line" => SvgShape::Path(read_svg_line(attributes)),
                     _ => panic!("unknown shape {} in svg", name.local_name),
                 };
+                let data = attributes
+                    .iter()
+                    .filter_map(|a| {
+                        let key = a.name.local_name.strip_prefix("data-")?;
+                        Some((key.to_owned(), a.value.clone()))
+                    })
+                    .collect();
                 items.push(SvgItem {
                     shape,
                     color,
                     index,
+                    data,
                 });
                 index += 1;
             }
@@ -110,6 +148,25 @@ pub fn read_svg(svg: &str) -> (Vec2, Vec<SvgItem>) {
     (pixel_to_meter(vec2(width, height)), items)
 }
 
+/// `data-*` attributes of the svg tag itself, keyed without the `data-` prefix.
+pub fn read_svg_data(svg: &str) -> HashMap<String, String> {
+    EventReader::from_str(svg)
+        .into_iter()
+        .find_map(|e| match e {
+            Ok(XmlEvent::StartElement {
+                name, attributes, ..
+            }) if name.local_name == "svg" => Some(attributes),
+            _ => None,
+        })
+        .expect("no svg tag")
+        .into_iter()
+        .filter_map(|a| {
+            let key = a.name.local_name.strip_prefix("data-")?.to_owned();
+            Some((key, a.value))
+        })
+        .collect()
+}
+
 fn get_attr<'a>(attributes: &'a [OwnedAttribute], name: &str) -> Option<&'a str> {
     attributes
         .iter()
@@ -174,6 +231,16 @@ pub fn read_svg_circle(attributes: &[OwnedAttribute]) -> CircleShape {
     }
 }
 
+pub fn read_svg_line(attributes: &[OwnedAttribute]) -> PathShape {
+    let parse_attr = |name| parse_attr(name, attributes).unwrap_or(0.0);
+
+    let start = vec2(parse_attr("x1"), parse_attr("y1"));
+    let end = vec2(parse_attr("x2"), parse_attr("y2"));
+    PathShape {
+        vertices: vec![pixel_to_meter(start), pixel_to_meter(end)],
+    }
+}
+
 use svg2polylines;
 use xml::writer::{EmitterConfig, EventWriter};
 pub fn read_svg_path(
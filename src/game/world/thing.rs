@@ -1,6 +1,7 @@
+use std::collections::HashSet;
 use std::ops::Mul;
 
-use hecs::{BuiltEntity, Component, EntityBuilder as HecsEntityBuilder};
+use hecs::{BuiltEntity, Component, Entity, EntityBuilder as HecsEntityBuilder};
 use macroquad::prelude::*;
 use rapier2d::prelude::*;
 
@@ -9,7 +10,9 @@ use super::{
     floor::{LazyCollider, Material},
     level::LevelId,
     light::{FlickerState, LightGroup, LightState, load_light},
+    one_way::one_way_collider,
     physics_world::PhysicsWorld,
+    prop::prop_thing,
 };
 use crate::{
     consts::*,
@@ -25,12 +28,15 @@ use crate::{
     },
 };
 
+/// Picked up when the player gets within its `AreaOfEffect`, and stays gone after that.
+pub struct Collectible;
+
 pub struct Mushroom {
-    pub touching_player: bool,
+    pub touching: HashSet<Entity>,
     pub rotation: f32,
 }
 pub struct Flytrap {
-    pub touching_player: bool,
+    pub touching: HashSet<Entity>,
     pub teeth_x: f32,
     pub teeth_speed: f32,
     pub flipped: bool,
@@ -38,7 +44,7 @@ pub struct Flytrap {
 impl Flytrap {
     fn new(flipped: bool) -> Self {
         Flytrap {
-            touching_player: false,
+            touching: HashSet::new(),
             teeth_x: 0.0,
             teeth_speed: 0.1,
             flipped,
@@ -145,13 +151,59 @@ pub fn thing_info_to_entity(
                 },
             )
             .add(Mushroom {
-                touching_player: false,
+                touching: HashSet::new(),
                 rotation,
             }),
         ],
         0x964952 => vec![t(world, "flytrap", Material::Fern).add(Flytrap::new(false))],
         0xB87C83 => vec![t(world, "flytrap-flipped", Material::Fern).add(Flytrap::new(true))],
+        0xA8743A => vec![prop_thing(
+            assets,
+            world,
+            pos,
+            rotation,
+            "crate",
+            Material::Mud,
+            PROP_CRATE_DENSITY,
+        )],
+        0x7A5230 => vec![prop_thing(
+            assets,
+            world,
+            pos,
+            rotation,
+            "log",
+            Material::Mud,
+            PROP_LOG_DENSITY,
+        )],
+        0x7C7F8C => vec![prop_thing(
+            assets,
+            world,
+            pos,
+            rotation,
+            "boulder",
+            Material::Stone,
+            PROP_BOULDER_DENSITY,
+        )],
         0x1C7D46 => create_bamboo(assets, world, pos, rotation, shape_size.height()),
+        COLLECTIBLE_COLOR => {
+            if world.collected.contains(&(level_id, thing_id)) {
+                vec![]
+            } else {
+                vec![
+                    tx(
+                        world,
+                        "berry",
+                        Material::Fern,
+                        BasicThingParams {
+                            collider: ColliderRepr::None,
+                            ..Default::default()
+                        },
+                    )
+                    .add(Collectible)
+                    .add(AreaOfEffect::new(COLLECTIBLE_PICKUP_RADIUS)),
+                ]
+            }
+        }
         _ => return None,
     })
 }
@@ -195,7 +247,11 @@ fn create_bamboo_segment(
 
     let (_, collider) = collider::load_collider(assets, "bamboo").unwrap();
 
-    let collider = environment_collider(collider, false);
+    let mut collider = environment_collider(collider, false);
+    // leaves can be jumped up through
+    if i >= big_leaf_cutoff {
+        collider = one_way_collider(collider);
+    }
 
     let handle = world
         .physics_world
@@ -258,7 +314,10 @@ fn respawn_thing(
 ) -> EntityBuilder {
     let down_dir = Vec2::from_angle(rotation).rotate(vec2(0.0, 1.0));
     let offset = down_dir * RESPAWN_INACTIVE_OFFSET;
-    let starts_active = world.player.all_respawns().contains(&(level_id, thing_id));
+    let starts_active = world
+        .respawns()
+        .all_respawns()
+        .contains(&(level_id, thing_id));
     let light = load_light(
         assets,
         texture,
@@ -294,9 +353,7 @@ fn respawn_thing(
         target_pos,
         offset,
     })
-    .add(RippleSource {
-        just_contacted: false,
-    })
+    .add(RippleSource)
     .add(AreaOfEffect::new(RESPAWN_AQUIRE_RADIUS))
 }
 
@@ -425,6 +482,7 @@ fn basic_thing(
                 rect,
                 builder: collider,
                 body_handle,
+                rest: Isometry::new(pos.into(), rotation),
             })
         } else {
             let handle = world
@@ -442,7 +500,7 @@ fn environment_collider(collider: ColliderBuilder, sensor: bool) -> ColliderBuil
         .friction_combine_rule(CoefficientCombineRule::Max)
         .collision_groups(InteractionGroups::new(
             COLLISION_LAYER_ENVIRONMENT.into(),
-            COLLISION_LAYER_PLAYER.into(),
+            (COLLISION_LAYER_PLAYER | COLLISION_LAYER_PROP).into(),
         ))
         .sensor(sensor)
 }
@@ -521,6 +579,9 @@ impl ThingInfo {
 }
 #[derive(Debug, Clone, PartialEq, Eq, Copy, Hash)]
 pub struct ThingId(pub usize);
+/// Which of the entities spawned for one thing this is, bamboo has one per segment.
+#[derive(Debug, Clone, PartialEq, Eq, Copy, Hash)]
+pub struct ThingPart(pub usize);
 
 pub fn spawn_thing(
     assets: &Assets,
@@ -530,8 +591,8 @@ pub fn spawn_thing(
     thing_id: ThingId,
     pos: Vec2,
     draw_layer: DrawLayer,
-) {
-    let Some(entities) = thing_info_to_entity(
+) -> Option<Entity> {
+    let entities = thing_info_to_entity(
         assets,
         world,
         thing_info.size,
@@ -540,11 +601,19 @@ pub fn spawn_thing(
         thing_info.rotate,
         level,
         thing_id,
-    ) else {
-        return;
-    };
-    for entity in entities {
-        let mut entity = entity.add(level).add(thing_id).add(draw_layer);
-        world.entities.spawn(entity.build());
-    }
+    )?;
+    entities
+        .into_iter()
+        .enumerate()
+        .map(|(part, entity)| {
+            let mut entity = entity
+                .add(level)
+                .add(thing_id)
+                .add(ThingPart(part))
+                .add(draw_layer);
+            world.entities.spawn(entity.build())
+        })
+        .collect::<Vec<_>>()
+        .first()
+        .copied()
 }
@@ -1,17 +1,20 @@
 use std::f32::consts::PI;
 
-use super::draw::pixel_to_meter;
+use super::draw::{self, pixel_to_meter};
+use super::events::{GameEvent, update_events};
 use super::floor::{LazyCollider, Material};
 use super::frame::Transition;
-use super::level::{
-    LevelId, load_level, unload_level, update_loaded_levels, update_loaded_levels_alive,
-};
+use super::level::{LevelId, level_at, load_level, unload_level, update_loaded_levels};
 use super::life_state::LifeState;
 use super::light::LightGroup;
-use super::physics_world::PhysicsWorld;
-use super::player::{Body, Polly, Rolly};
+use super::platform::update_platforms;
+use super::player::{Body, Launchable, Player, Polly, Rolly};
+use super::prop::Prop;
+use super::schedule::{RunIf, Schedule, System, SystemContext};
+use super::signal::{update_receivers, update_triggers};
 
 use super::World;
+use super::camera::CameraZone;
 use super::thing::{AreaOfEffect, Respawn, ThingId};
 use crate::consts::*;
 use crate::game::Settings;
@@ -19,7 +22,10 @@ use crate::game::assets::Assets;
 use crate::game::config::GameConfig;
 use crate::game::world::level::load_level_at_pos;
 use crate::game::world::light::{LightState, Ripple, RippleSource, RippleState};
-use crate::game::world::thing::{Flytrap, Mushroom, RespawnActive, ThingDraw};
+use crate::game::world::thing::{Collectible, Flytrap, Mushroom, RespawnActive, ThingDraw};
+use crate::game::world::water::WaterSurface;
+use crate::game::world::wind::Wind;
+use hecs::Entity;
 use macroquad::prelude::*;
 use nalgebra::UnitComplex;
 use ordered_float::OrderedFloat;
@@ -31,65 +37,135 @@ pub async fn update(
     world: &mut World,
     config: &GameConfig,
 ) {
-    update_camera(settings, world);
-
-    update_loaded_levels_alive(assets, world);
-    update_lazy_collider(world);
-
-    world.physics_world.update();
-
-    player_body(world);
-    player_water(world);
-    player_mushroom(world);
-    player_flytrap(world);
-    update_ripple_source(world);
-    player_fall(world);
+    let mut schedule = std::mem::take(&mut world.schedule);
+    schedule.run(&mut SystemContext {
+        assets,
+        settings,
+        config,
+        world,
+    });
+    world.schedule = schedule;
+    // reloading assets has to wait for the files, so it can't be a system
     if config.cheat {
-        player_cheat_movement(world);
         player_cheat_assets(assets, world).await;
     }
+}
 
-    player_transition(world);
-    respawn_transition(world);
-
-    player_respawn(world);
-
-    update_life_state(assets, world);
-    update_back(world, assets);
-    update_light(world);
-    update_ripple(world);
-    update_flytrap_teeth(world);
+/// Every system that runs in `update`.
+pub fn schedule() -> Schedule {
+    Schedule::new(vec![
+        System::new("camera", |ctx| update_camera(ctx.settings, ctx.world)),
+        System::new("light map", |ctx| {
+            if let Some(light_map) = &mut ctx.world.light_map {
+                light_map.fit_screen();
+            }
+        })
+        .after(&["camera"]),
+        System::new("levels", |ctx| update_loaded_levels(ctx.assets, ctx.world))
+            .run_if(RunIf::Alive),
+        System::new("lazy colliders", |ctx| update_lazy_collider(ctx.world)).after(&["levels"]),
+        System::new("receivers", |ctx| {
+            update_receivers(ctx.world, get_frame_time())
+        }),
+        System::new("platforms", |ctx| {
+            update_platforms(ctx.world, get_frame_time())
+        })
+        .after(&["receivers"]),
+        System::new("physics", |ctx| step_physics(ctx.world))
+            .after(&["lazy colliders", "platforms"]),
+        System::new("events", |ctx| update_events(ctx.world)).after(&["physics"]),
+        System::new("triggers", |ctx| update_triggers(ctx.world)).after(&["events"]),
+        System::new("player body", |ctx| player_body(ctx.world))
+            .run_if(RunIf::Alive)
+            .after(&["physics"]),
+        System::new("player ground", |ctx| player_ground(ctx.world)).after(&["events"]),
+        System::new("water", |ctx| update_water(ctx.world)).after(&["physics"]),
+        System::new("player water", |ctx| player_water(ctx.world)).after(&["events", "water"]),
+        System::new("prop water", |ctx| prop_water(ctx.world)).after(&["events", "water"]),
+        System::new("prop fall", |ctx| prop_fall(ctx.world, ctx.assets)).after(&["physics"]),
+        System::new("wind", |ctx| update_wind(ctx.world)),
+        System::new("player wind", |ctx| player_wind(ctx.world))
+            .run_if(RunIf::Alive)
+            .after(&["physics"]),
+        System::new("mushrooms", |ctx| update_mushrooms(ctx.world)).after(&["physics"]),
+        System::new("flytraps", |ctx| update_flytraps(ctx.world)).after(&["physics"]),
+        System::new("ripple sources", |ctx| update_ripple_source(ctx.world)).after(&["events"]),
+        System::new("impacts", |ctx| player_impact(ctx.world)).after(&["events"]),
+        System::new("current level", |ctx| {
+            update_current_level(ctx.world, ctx.assets)
+        })
+        .after(&["physics"]),
+        System::new("fall", |ctx| player_fall(ctx.world, ctx.assets))
+            .run_if(RunIf::Alive)
+            .after(&["current level"]),
+        System::new("cheat movement", |ctx| player_cheat_movement(ctx.world))
+            .run_if(RunIf::Cheat)
+            .after(&["physics"]),
+        System::new("player transition", |ctx| player_transition(ctx.world)),
+        System::new("respawn transition", |ctx| respawn_transition(ctx.world)),
+        System::new("respawns", |ctx| player_respawn(ctx.world))
+            .run_if(RunIf::Alive)
+            .after(&["physics"]),
+        System::new("collectibles", |ctx| player_collect(ctx.world))
+            .run_if(RunIf::Alive)
+            .after(&["physics"]),
+        System::new("life state", |ctx| update_life_state(ctx.assets, ctx.world)).after(&[
+            "player water",
+            "fall",
+            "respawns",
+        ]),
+        System::new("background", |ctx| update_back(ctx.world)).after(&["current level"]),
+        System::new("speedrun", |ctx| update_speedrun(ctx.world)).after(&["life state"]),
+        System::new("ghost", |ctx| update_ghost(ctx.world)).after(&["speedrun"]),
+        System::new("lights", |ctx| update_light(ctx.world)).after(&["ripple sources"]),
+        System::new("ripples", |ctx| update_ripple(ctx.world)).after(&["lights"]),
+        System::new("flytrap teeth", |ctx| update_flytrap_teeth(ctx.world)).after(&["flytraps"]),
+        System::new("feet", |ctx| player_feet_grounded(ctx.world))
+            .run_if(RunIf::Polly)
+            .after(&["events", "player body"]),
+        System::new("feet frame", |ctx| player_feet_frame(ctx.world))
+            .run_if(RunIf::Polly)
+            .after(&["feet"]),
+        System::new("direction", |ctx| player_direction(ctx.world)).run_if(RunIf::PollyAlive),
+        System::new("movement", |ctx| player_movement(ctx.world))
+            .run_if(RunIf::Polly)
+            .after(&["feet", "player ground"]),
+        System::new("drop through", |ctx| player_drop_through(ctx.world))
+            .run_if(RunIf::Polly)
+            .after(&["feet"]),
+    ])
+}
 
-    match world.player.body {
-        Body::Rolly(_) => {}
-        Body::Polly(_) => player_polly(world),
-    }
+fn step_physics(world: &mut World) {
+    let dropping = match &*world.form() {
+        Body::Polly(polly) if polly.drop_through > 0.0 => Some(polly.body_handle),
+        _ => None,
+    };
+    world.physics_world.physics_hooks.dropping = dropping;
+    world.physics_world.update();
 }
 fn update_ripple_source(world: &mut World) {
-    let mut ripples = Vec::new();
     let player_pos = Vec2::from(get_player_body(world).position().translation);
-    for (_, (handle, source)) in world
-        .entities
-        .query_mut::<(&RigidBodyHandle, &mut RippleSource)>()
-    {
-        let body = world.physics_world.rigid_body_set.get(*handle).unwrap();
-        let contact = body
-            .colliders()
-            .iter()
-            .flat_map(|collider| {
-                world
-                    .physics_world
-                    .narrow_phase
-                    .contact_pairs_with(*collider)
-            })
-            .find(|pair| pair.has_any_active_contact);
-        if !source.just_contacted && contact.is_some() {
-            ripples.push(Ripple::new(player_pos));
-        }
-        source.just_contacted = contact.is_some();
+    let ripples = world
+        .contacts
+        .events
+        .iter()
+        .filter(|event| match event {
+            GameEvent::PlayerTouched { entity } => {
+                world.entities.get::<&RippleSource>(*entity).is_ok()
+            }
+            _ => false,
+        })
+        .count();
+    for _ in 0..ripples {
+        world.entities.spawn((Ripple::new(player_pos),));
     }
-    for ripple in ripples {
-        world.entities.spawn((ripple,));
+}
+fn player_impact(world: &mut World) {
+    for event in world.contacts.events.iter() {
+        if let GameEvent::Impact { impulse, .. } = event {
+            world.camera_controller.impact(*impulse);
+        }
     }
 }
 fn update_ripple(world: &mut World) {
@@ -113,23 +189,57 @@ fn update_flytrap_teeth(world: &mut World) {
 }
 
 fn update_camera(settings: &Settings, world: &mut World) {
-    if let LifeState::Alive(Transition::End)
-    | LifeState::Dead(Transition::Start | Transition::Between { .. }) = world.player.life_state
-    {
-        let camera_target: Vec2 = (*world
-            .physics_world
-            .get_body(world.player.body.any_body_handle())
-            .unwrap()
-            .translation())
-        .into();
-        let diff = camera_target - world.camera.target;
-        world.camera.target += diff * settings.camera_speed.value;
+    let dt = get_frame_time();
+    let base_zoom = camera_zoom(settings);
+    let mut zoom = world.camera_controller.zoom;
+    let following = matches!(
+        *world.life_state(),
+        LifeState::Alive(Transition::End)
+            | LifeState::Dead(Transition::Start | Transition::Between { .. })
+    );
+    if following {
+        let body = get_player_body(world);
+        let player_pos: Vec2 = (*body.translation()).into();
+        let linvel: Vec2 = (*body.linvel()).into();
+        let grounded = world.player().ground.is_some();
+        let zone = world
+            .entities
+            .query_mut::<&CameraZone>()
+            .into_iter()
+            .map(|(_, zone)| zone)
+            .find(|zone| zone.area.contains(player_pos))
+            .cloned();
+
+        let controller = &mut world.camera_controller;
+        controller.follow(player_pos, linvel, grounded, dt);
+        let mut goal = controller.focus + vec2(controller.look_ahead, 0.0);
+        zoom = 1.0;
+        if let Some(zone) = zone {
+            let center = zone.area.center();
+            if zone.frame {
+                goal = center;
+                zoom = (2.0 / (zone.area.w * base_zoom.x)).min(2.0 / (zone.area.h * base_zoom.y));
+            }
+            if zone.lock_x {
+                goal.x = center.x;
+            }
+            if zone.lock_y {
+                goal.y = center.y;
+            }
+            zoom *= zone.zoom.unwrap_or(1.0);
+        }
+        controller.pos += (goal - controller.pos) * settings.camera_speed.value;
     }
+    let controller = &mut world.camera_controller;
+    controller.zoom += (zoom - controller.zoom) * (CAMERA_ZOOM_SPEED * dt).min(1.0);
+    let (shake_offset, shake_rotation) = controller.tick_shake(dt);
 
     world.camera = Camera2D {
+        target: controller.pos + shake_offset,
+        rotation: shake_rotation,
         // zoom: camera_zoom(world), — works in macroquad 0.4.*
         // need to use this to make it work in 0.3.*, to address screen flipping bug
-        zoom: vec2(camera_zoom(settings).x, -camera_zoom(settings).y),
+        zoom: vec2(base_zoom.x, -base_zoom.y) * controller.zoom,
         ..world.camera
     };
 }
@@ -143,45 +253,43 @@ pub fn camera_zoom(settings: &Settings) -> Vec2 {
 
 use super::player::Direction;
 fn player_direction(world: &mut World) {
-    if let LifeState::Alive(Transition::End) = world.player.life_state {
-        match (is_key_down(KeyCode::Right), is_key_down(KeyCode::Left)) {
-            (true, false) => {
-                world.player.eye_x.set(1.0);
-                world.player.direction = Direction::Right;
-            }
-            (false, true) => {
-                world.player.eye_x.set(-1.0);
-                world.player.direction = Direction::Left;
-            }
-            _ => (),
+    let mut player = world.player_mut();
+    match (is_key_down(KeyCode::Right), is_key_down(KeyCode::Left)) {
+        (true, false) => {
+            player.eye_x.set(1.0);
+            player.direction = Direction::Right;
+        }
+        (false, true) => {
+            player.eye_x.set(-1.0);
+            player.direction = Direction::Left;
         }
+        _ => (),
     }
 }
 
 fn player_feet_frame(world: &mut World) {
-    let polly = world.player.body.unwrap_polly_mut();
+    let mut form = world.form_mut();
+    let polly = form.unwrap_polly_mut();
     let body = world.physics_world.get_body(polly.body_handle).unwrap();
     polly.feet_frame -= body.linvel().x * if polly.feet_grounded[1] { 0.1 } else { 0.02 };
 }
 
 fn player_body(world: &mut World) {
-    if !world.player.alive() {
-        return;
-    }
     if is_key_pressed(KeyCode::Down) {
         let body = world
             .physics_world
-            .get_body_mut(world.player.body.any_body_handle())
+            .get_body_mut(world.player_body_handle())
             .unwrap();
         let translation = *body.translation();
         let rotation = *body.rotation();
         let linvel = *body.linvel();
         let angvel = body.angvel();
-        match world.player.body.clone() {
+        let form = Body::clone(&world.form());
+        let form = match form {
             Body::Polly(polly) => {
                 polly.despawn(&mut world.physics_world);
-                world.player.rolly_polly_transition.run(0.3, false);
-                world.player.body = Body::Rolly(Rolly::spawn(
+                world.player_mut().rolly_polly_transition.run(0.3, false);
+                Body::Rolly(Rolly::spawn(
                     &mut world.physics_world,
                     translation.into(),
                     rotation.angle(),
@@ -191,8 +299,8 @@ fn player_body(world: &mut World) {
             }
             Body::Rolly(rolly) => {
                 rolly.despawn(&mut world.physics_world);
-                world.player.rolly_polly_transition.run(0.3, true);
-                world.player.body = Body::Polly(Polly::spawn(
+                world.player_mut().rolly_polly_transition.run(0.3, true);
+                Body::Polly(Polly::spawn(
                     &mut world.physics_world,
                     translation.into(),
                     rotation.angle(),
@@ -201,12 +309,51 @@ fn player_body(world: &mut World) {
                 ))
             }
         };
+        Player::set_body(world, form);
+    }
+}
+
+/// Materials of whatever `player_collider` touches, or any player collider if `None`.
+fn touched_materials(
+    world: &World,
+    player_collider: Option<ColliderHandle>,
+) -> impl Iterator<Item = Material> + '_ {
+    world
+        .contacts
+        .touching(&world.physics_world, player_collider)
+        .filter_map(|entity| world.entities.get::<&Material>(entity).ok().map(|m| *m))
+}
+
+fn player_ground(world: &mut World) {
+    let ground = touched_materials(world, None).find(|material| material.rigid());
+    world.player_mut().ground = ground;
+
+    // rolly's own friction wins over the floor's, so scale it by the grip of the ground
+    let grip = ground.map_or(1.0, Material::grip);
+    let friction = world.form().base_friction() * grip;
+    let collider = world.form().any_collider_handle();
+    world
+        .physics_world
+        .collider_set
+        .get_mut(collider)
+        .unwrap()
+        .set_friction(friction);
+
+    let drag = ground.map_or(0.0, Material::drag);
+    if drag > 0.0 {
+        let body = get_player_body_mut(world);
+        let linvel = *body.linvel();
+        body.set_linvel(
+            linvel - linvel * (drag * get_frame_time()).clamp(0.0, 1.0),
+            true,
+        );
     }
 }
 
 fn player_transition(world: &mut World) {
-    world.player.rolly_polly_transition.tick(get_frame_time());
-    world.player.eye_x.tick(get_frame_time());
+    let mut player = world.player_mut();
+    player.rolly_polly_transition.tick(get_frame_time());
+    player.eye_x.tick(get_frame_time());
 }
 fn respawn_transition(world: &mut World) {
     for (_, (respawn, draw, light_group)) in
@@ -232,15 +379,18 @@ fn respawn_transition(world: &mut World) {
     }
 }
 
-fn player_polly(world: &mut World) {
-    player_feet_grounded(world);
-    player_feet_frame(world);
-    player_direction(world);
-    player_movement(world);
+fn player_drop_through(world: &mut World) {
+    let alive = world.player_alive();
+    let mut form = world.form_mut();
+    let polly = form.unwrap_polly_mut();
+    polly.drop_through = (polly.drop_through - get_frame_time()).max(0.0);
+    if alive && is_key_pressed(KeyCode::Space) && polly.feet_grounded[1] {
+        polly.drop_through = PLAYER_DROP_THROUGH_TIME;
+    }
 }
 const CHEAT_MOVE_SPEED: f32 = 0.1;
 fn player_cheat_movement(world: &mut World) {
-    let handle = world.player.body.any_body_handle();
+    let handle = world.player_body_handle();
     let rigid_body = world.physics_world.get_body_mut(handle).unwrap();
     let mut pos = *rigid_body.position();
     let mut used = false;
@@ -313,12 +463,8 @@ fn find_closest_respawn(world: &World, pos: Vec2, filt: impl Fn(&Vec2) -> bool)
         .min_by_key(|respawn_pos| OrderedFloat::from(respawn_pos.distance_squared(pos)))
 }
 fn player_movement(world: &mut World) {
-    let alive = if let LifeState::Alive(Transition::End) = world.player.life_state {
-        true
-    } else {
-        false
-    };
-    let polly = world.player.body.unwrap_polly_mut();
+    let alive = world.player_alive();
+    let polly = world.form().unwrap_polly().clone();
     let [
         left_feet_grounded,
         center_feet_grounded,
@@ -344,9 +490,10 @@ fn player_movement(world: &mut World) {
         _ => None,
     };
 
+    let control = world.player().ground.map_or(1.0, Material::control);
     if let Some(dir) = movement_state {
         let vel = if center_feet_grounded {
-            PLAYER_VEL_X_GROUNDED
+            PLAYER_VEL_X_GROUNDED * control
         } else {
             PLAYER_VEL_X
         };
@@ -360,7 +507,7 @@ fn player_movement(world: &mut World) {
     }
 
     if center_feet_grounded {
-        let delta = linvel * 0.8 * get_frame_time();
+        let delta = linvel * 0.8 * control * get_frame_time();
         linvel -= delta;
 
         // let delta = angvel * 0.8 * get_frame_time();
@@ -389,100 +536,127 @@ fn player_movement(world: &mut World) {
 }
 
 fn player_feet_grounded(world: &mut World) {
-    let polly = world.player.body.unwrap_polly();
-    let mut get = |index: usize| {
-        for (_, (entity_collider, _)) in world
-            .entities
-            .query_mut::<(&ColliderHandle, &Material)>()
-            .into_iter()
-            .filter(|(_, (_, material))| material.rigid())
-        {
-            if collider_intersecting(
-                &world.physics_world,
-                *entity_collider,
-                polly.feet_sensor_handles[index],
-            ) {
-                return true;
-            }
-        }
-        false
-    };
-    let feet_grounded = [get(0), get(1), get(2)];
-    let polly = world.player.body.unwrap_polly_mut();
-    polly.feet_grounded = feet_grounded;
-}
-
-fn collider_intersecting(
-    physics_world: &PhysicsWorld,
-    handle_1: ColliderHandle,
-    handle_2: ColliderHandle,
-) -> bool {
-    physics_world
-        .narrow_phase
-        .intersection_pair(handle_1, handle_2)
-        == Some(true)
+    let feet = world.form().unwrap_polly().feet_sensor_handles;
+    let feet_grounded =
+        feet.map(|foot| touched_materials(world, Some(foot)).any(|material| material.rigid()));
+    world.form_mut().unwrap_polly_mut().feet_grounded = feet_grounded;
 }
 
 fn player_water(world: &mut World) {
-    let player_collider = world.player.body.any_collider_handle();
-    let in_water = 'outer: {
-        for (_, (collider_handle, _)) in world
-            .entities
-            .query_mut::<(&ColliderHandle, &Material)>()
-            .into_iter()
-            .filter(|(_, (_, material))| matches! {material, Material::Water})
-        {
-            if collider_intersecting(&world.physics_world, *collider_handle, player_collider) {
-                break 'outer true;
-            }
-        }
-        false
-    };
-    let player_body = world.player.body.any_body_handle();
+    let player_collider = world.form().any_collider_handle();
+    let in_water = touched_materials(world, Some(player_collider))
+        .any(|material| matches!(material, Material::Water));
+    let player_pos = Vec2::from(get_player_body(world).position().translation);
+    let depth = water_depth(world, player_pos);
+    // polly can't swim
+    let drowning = in_water && world.player_alive() && world.form().is_polly();
+    let player_body = world.player_body_handle();
     let player_body = world.physics_world.get_body_mut(player_body).unwrap();
     let mut linvel = *player_body.linvel();
     if in_water {
-        linvel.x -= linvel.x * (0.5 * get_frame_time()).clamp(0.0, 1.0);
-        linvel.y -= linvel.y * (0.5 * get_frame_time()).clamp(0.0, 1.0);
-        linvel.y -= 8.0 * get_frame_time();
-        if let LifeState::Alive(Transition::End) = world.player.life_state
-            && let Body::Polly(_) = world.player.body
-        {
-            world.player.life_state = LifeState::Dead(Transition::Start);
-        }
+        linvel.x -= linvel.x * (WATER_DRAG * get_frame_time()).clamp(0.0, 1.0);
+        linvel.y -= linvel.y * (WATER_DRAG * get_frame_time()).clamp(0.0, 1.0);
+        let buoyancy = (depth / WATER_BUOYANCY_DEPTH).clamp(0.0, 1.0);
+        linvel.y -= WATER_BUOYANCY * buoyancy * get_frame_time();
     }
     player_body.set_linvel(linvel, true);
+    if drowning {
+        *world.life_state_mut() = LifeState::Dead(Transition::Start);
+    }
 }
 
-fn update_life_state(assets: &Assets, world: &mut World) {
-    let (LifeState::Alive(transition) | LifeState::Dead(transition)) = &mut world.player.life_state;
-    let old_transition = transition.clone();
-    if let Transition::Start = transition {
-        transition.run(DEAD_ALIVE_TRANSITION_DURATION, true);
+/// Floats props lighter than water and sinks heavier ones, by how much of them is under.
+fn prop_water(world: &mut World) {
+    let floating = world
+        .entities
+        .query::<(&Prop, &RigidBodyHandle, &ColliderHandle)>()
+        .iter()
+        .filter(|(_, (_, _, collider))| {
+            world
+                .contacts
+                .prop_overlapping(&world.physics_world, **collider)
+                .any(|entity| {
+                    world
+                        .entities
+                        .get::<&Material>(entity)
+                        .is_ok_and(|material| matches!(*material, Material::Water))
+                })
+        })
+        .map(|(_, (prop, body, _))| {
+            let pos = Vec2::from(*world.physics_world.get_body(*body).unwrap().translation());
+            let depth = water_depth(world, pos);
+            let submerged = ((depth + prop.radius) / (prop.radius * 2.0)).clamp(0.0, 1.0);
+            (*body, prop.density, submerged)
+        })
+        .collect::<Vec<_>>();
+    let gravity = world.physics_world.gravity.y;
+    let drag = (WATER_DRAG * get_frame_time()).clamp(0.0, 1.0);
+    for (body, density, submerged) in floating {
+        let body = world.physics_world.get_body_mut(body).unwrap();
+        let mut linvel = *body.linvel();
+        linvel -= linvel * drag;
+        linvel.y -= gravity * WATER_DENSITY / density * submerged * get_frame_time();
+        body.set_linvel(linvel, true);
+        body.set_angvel(body.angvel() * (1.0 - drag), true);
     }
-    transition.tick(get_frame_time());
-    match (old_transition, world.player.life_state.clone()) {
+}
+
+/// Depth of `pos` under the closest wave surface above it, so buoyancy follows the waves.
+fn water_depth(world: &World, pos: Vec2) -> f32 {
+    world
+        .entities
+        .query::<&WaterSurface>()
+        .iter()
+        .filter_map(|(_, surface)| surface.height_at(pos.x))
+        .map(|height| pos.y - height)
+        .filter(|depth| *depth > -WATER_SPLASH_RADIUS)
+        .min_by_key(|depth| OrderedFloat(*depth))
+        .unwrap_or(WATER_BUOYANCY_DEPTH)
+}
+
+fn update_water(world: &mut World) {
+    let body = get_player_body(world);
+    let player_pos = Vec2::from(body.position().translation);
+    let player_vel = Vec2::from(*body.linvel());
+    for (_, surface) in world.entities.query_mut::<&mut WaterSurface>() {
+        surface.interact(player_pos, player_vel, get_frame_time());
+        surface.update(get_frame_time());
+    }
+}
+
+fn update_life_state(assets: &Assets, world: &mut World) {
+    let (old_transition, life_state) = {
+        let mut life_state = world.life_state_mut();
+        let (LifeState::Alive(transition) | LifeState::Dead(transition)) = &mut *life_state;
+        let old_transition = transition.clone();
+        if let Transition::Start = transition {
+            transition.run(DEAD_ALIVE_TRANSITION_DURATION, true);
+        }
+        transition.tick(get_frame_time());
+        (old_transition, life_state.clone())
+    };
+    match (old_transition, life_state) {
         (Transition::Between { .. }, LifeState::Alive(Transition::End)) => {
             respawn_player(world);
         }
         (_, LifeState::Dead(Transition::End)) => {
             load_respawn(assets, world);
-            world.player.life_state = LifeState::Alive(Transition::Start);
+            *world.life_state_mut() = LifeState::Alive(Transition::Start);
         }
         _ => (),
     }
 }
 
 pub fn respawn_player(world: &mut World) {
-    world.player.reset(&mut world.physics_world);
+    Player::reset(world);
 
-    let (pos, rotation) = find_respawn(world, world.player.respawn());
+    let (pos, rotation) = find_respawn(world, world.respawns().respawn());
     let angle_up = UnitComplex::from_angle(rotation + PI);
 
     let pos = Vector::from(pos) + angle_up.transform_vector(&vector![0.0, pixel_to_meter(45.0)]);
     let linvel = angle_up.transform_vector(&vector![0.0, PLAYER_RESPAWN_BOOST]);
 
-    let body = world.player.body.any_body_handle();
+    let body = world.player_body_handle();
     let body = world.physics_world.get_body_mut(body).unwrap();
 
     body.set_translation(pos, true);
@@ -493,9 +667,13 @@ fn load_respawn(assets: &Assets, world: &mut World) {
     for (level, _) in world.levels.clone() {
         unload_level(world, level)
     }
-    load_level(assets, world, world.player.respawn().0);
+    // dying puts every level back how it started, so a prop pushed into a pit can't get
+    // the player stuck, snapshots only carry levels across camera unloads
+    world.snapshots.clear();
+    let respawn = world.respawns().respawn();
+    load_level(assets, world, respawn.0);
 
-    let (pos, _) = find_respawn(world, world.player.respawn());
+    let (pos, _) = find_respawn(world, respawn);
     world.camera.target = pos;
 
     update_loaded_levels(assets, world);
@@ -518,20 +696,18 @@ fn find_respawn(world: &World, respawn: (LevelId, ThingId)) -> (Vec2, f32) {
 }
 
 fn get_player_body(world: &World) -> &RigidBody {
-    let body = world.player.body.any_body_handle();
+    let body = world.player_body_handle();
     world.physics_world.get_body(body).unwrap()
 }
 fn get_player_body_mut(world: &mut World) -> &mut RigidBody {
-    let body = world.player.body.any_body_handle();
+    let body = world.player_body_handle();
     world.physics_world.get_body_mut(body).unwrap()
 }
 
 fn player_respawn(world: &mut World) {
-    if !world.player.alive() {
-        return;
-    }
     let body = get_player_body(world);
     let player_pos: Vec2 = (*body.translation()).into();
+    let mut reached = Vec::new();
     for (_, (respawn, _, _, level_id, thing_id, _light_group)) in world
         .entities
         .query_mut::<(
@@ -547,7 +723,7 @@ fn player_respawn(world: &mut World) {
             area.contains(handle, &world.physics_world, player_pos)
         })
     {
-        world.player.set_respawn((*level_id, *thing_id));
+        reached.push((*level_id, *thing_id));
 
         respawn.active = match respawn.active.clone() {
             RespawnActive::Inactive => RespawnActive::Active(Transition::running(
@@ -557,92 +733,201 @@ fn player_respawn(world: &mut World) {
             other => other,
         }
     }
+    for respawn in reached {
+        world.respawns_mut().set_respawn(respawn);
+    }
     if is_key_pressed(KeyCode::R) {
-        world.player.life_state = LifeState::Dead(Transition::Start);
+        *world.life_state_mut() = LifeState::Dead(Transition::Start);
     }
 }
-fn player_mushroom(world: &mut World) {
-    if !world.player.alive() {
-        return;
+fn update_wind(world: &mut World) {
+    for (_, wind) in world.entities.query_mut::<&mut Wind>() {
+        wind.update_particles(get_frame_time());
     }
-    let body = get_player_body(world);
-    let mut linvel: Vec2 = (*body.linvel()).into();
-    let player_pos: Vec2 = (*body.translation()).into();
+}
+fn player_wind(world: &mut World) {
+    let player_pos = Vec2::from(get_player_body(world).position().translation);
+    // the ball catches more of the current than polly
+    let factor = if world.form().is_rolly() {
+        WIND_ROLLY_FACTOR
+    } else {
+        WIND_POLLY_FACTOR
+    };
+    let accel = world
+        .entities
+        .query_mut::<&Wind>()
+        .into_iter()
+        .filter(|(_, wind)| wind.contains(player_pos))
+        .fold(Vec2::ZERO, |accel, (_, wind)| {
+            accel + wind.dir * wind.strength
+        });
+    let body = get_player_body_mut(world);
+    let linvel = Vec2::from(*body.linvel()) + accel * factor * get_frame_time();
+    body.set_linvel(linvel.into(), true);
+}
+fn player_collect(world: &mut World) {
+    let player_pos = Vec2::from(get_player_body(world).position().translation);
+    let picked = world
+        .entities
+        .query_mut::<(
+            &Collectible,
+            &RigidBodyHandle,
+            &AreaOfEffect,
+            &LevelId,
+            &ThingId,
+        )>()
+        .into_iter()
+        .filter(|(_, (_, handle, area, _, _))| {
+            area.contains(handle, &world.physics_world, player_pos)
+        })
+        .map(|(entity, (_, handle, _, level, thing))| (entity, *handle, *level, *thing))
+        .collect::<Vec<_>>();
+    for (entity, handle, level, thing) in picked {
+        world.collected.insert((level, thing));
+        let pos = Vec2::from(*world.physics_world.get_body(handle).unwrap().translation());
+        world.physics_world.remove_body(handle);
+        world.entities.despawn(entity).unwrap();
+        world.entities.spawn((Ripple::new(pos),));
+    }
+}
+/// Launchable bodies that can be launched right now, and whether they are rolling.
+fn launchables(world: &World) -> Vec<(Entity, RigidBodyHandle, bool)> {
+    world
+        .entities
+        .query::<(
+            &Launchable,
+            &RigidBodyHandle,
+            Option<&Body>,
+            Option<&LifeState>,
+        )>()
+        .iter()
+        .filter(|(_, (_, _, _, life_state))| life_state.is_none_or(LifeState::alive))
+        .map(|(entity, (_, handle, body, _))| (entity, *handle, body.is_none_or(Body::is_rolly)))
+        .collect()
+}
+fn update_mushrooms(world: &mut World) {
+    let launchables = launchables(world);
     for (_, (mushroom, mushroom_handle)) in world
         .entities
         .query_mut::<(&mut Mushroom, &RigidBodyHandle)>()
     {
         let mushroom_body = world.physics_world.get_body(*mushroom_handle).unwrap();
         let mushroom_pos: Vec2 = (*mushroom_body.translation()).into();
-        if player_pos.distance(mushroom_pos) < 0.2 {
-            if mushroom.touching_player {
+        for &(entity, handle, _) in launchables.iter() {
+            let body = world.physics_world.get_body_mut(handle).unwrap();
+            let pos: Vec2 = (*body.translation()).into();
+            if pos.distance(mushroom_pos) >= 0.2 {
+                mushroom.touching.remove(&entity);
+                continue;
+            }
+            if !mushroom.touching.insert(entity) {
                 continue;
             }
-            mushroom.touching_player = true;
             let dir = Vec2::from_angle(mushroom.rotation).rotate(Vec2::new(0.0, -1.0));
 
+            let mut linvel: Vec2 = (*body.linvel()).into();
             let current_speed_in_dir = linvel.dot(dir);
 
             let velocity_delta = PLAYER_VEL_MUSHROOM - current_speed_in_dir;
 
             linvel += dir * velocity_delta;
-        } else {
-            mushroom.touching_player = false;
+            body.set_linvel(linvel.into(), true);
+            if entity == world.player {
+                world.camera_controller.shake(CAMERA_SHAKE_MUSHROOM);
+            }
         }
     }
-    let body = get_player_body_mut(world);
-    body.set_linvel(linvel.into(), true);
 }
-fn player_flytrap(world: &mut World) {
-    if !world.player.alive() {
-        return;
-    }
-    let body = get_player_body(world);
-    let mut angvel: f32 = body.angvel();
-    let player_pos: Vec2 = (*body.translation()).into();
-    let player_is_rolly = world.player.body.is_rolly();
+fn update_flytraps(world: &mut World) {
+    let launchables = launchables(world);
     for (_, (flytrap, flytrap_handle)) in world
         .entities
         .query_mut::<(&mut Flytrap, &RigidBodyHandle)>()
     {
         let flytrap_body = world.physics_world.get_body(*flytrap_handle).unwrap();
         let flytrap_pos: Vec2 = (*flytrap_body.translation()).into();
-        if player_pos.distance(flytrap_pos) < 0.2 {
-            if flytrap.touching_player {
+        for &(entity, handle, rolling) in launchables.iter() {
+            let body = world.physics_world.get_body_mut(handle).unwrap();
+            let pos: Vec2 = (*body.translation()).into();
+            if pos.distance(flytrap_pos) >= 0.2 {
+                flytrap.touching.remove(&entity);
                 continue;
             }
-            flytrap.touching_player = true;
-            if player_is_rolly {
-                angvel = if flytrap.flipped {
+            if !flytrap.touching.insert(entity) {
+                continue;
+            }
+            if rolling {
+                let angvel = if flytrap.flipped {
                     -PLAYER_VEL_FLYTRAP
                 } else {
                     PLAYER_VEL_FLYTRAP
                 };
+                body.set_angvel(angvel, true);
                 flytrap.teeth_speed = 2.0;
+                if entity == world.player {
+                    world.camera_controller.shake(CAMERA_SHAKE_FLYTRAP);
+                }
             } else {
                 flytrap.teeth_speed = 1.0;
             }
-        } else {
-            flytrap.touching_player = false;
         }
     }
-    let body = get_player_body_mut(world);
-    body.set_angvel(angvel, true);
 }
-fn player_fall(world: &mut World) {
-    if !world.player.alive() {
-        return;
+/// Puts props that fell out of their level back where they spawned.
+fn prop_fall(world: &mut World, assets: &Assets) {
+    let fallen = world
+        .entities
+        .query::<(&Prop, &RigidBodyHandle, &LevelId)>()
+        .iter()
+        .filter(|(_, (_, body, level))| {
+            let pos = Vec2::from(*world.physics_world.get_body(**body).unwrap().translation());
+            let bottom = world.levels[*level].y + assets.levels[&level.0].0.dims.y;
+            level_at(assets, world, pos).is_none() && pos.y > bottom + FALL_MARGIN
+        })
+        .map(|(_, (prop, body, _))| (*body, prop.spawn))
+        .collect::<Vec<_>>();
+    for (body, spawn) in fallen {
+        let body = world.physics_world.get_body_mut(body).unwrap();
+        body.set_position(spawn, true);
+        body.set_linvel(vector![0.0, 0.0], true);
+        body.set_angvel(0.0, true);
     }
+}
+
+/// Kills the player once they fall out the bottom of the level they were last in, unless
+/// another level below caught them.
+fn player_fall(world: &mut World, assets: &Assets) {
     let body = get_player_body(world);
     let player_pos: Vec2 = (*body.translation()).into();
-    if player_pos.y > 10.0 {
-        world.player.life_state = LifeState::Dead(Transition::Start);
+    if level_at(assets, world, player_pos).is_some() {
+        return;
+    }
+    if player_pos.y > world.current_bounds.bottom() + FALL_MARGIN {
+        *world.life_state_mut() = LifeState::Dead(Transition::Start);
     }
 }
 
 fn update_lazy_collider(world: &mut World) {
     let mut entities_remove_collider = Vec::new();
     let mut entities_add_collider = Vec::new();
+    // the player and every prop need the environment around them, with the size to load
+    let mut loaders = vec![(
+        Vec2::from(get_player_body(world).position().translation),
+        LAZY_PLAYER_RECT,
+    )];
+    loaders.extend(
+        world
+            .entities
+            .query::<(&Prop, &RigidBodyHandle)>()
+            .iter()
+            .map(|(_, (prop, body))| {
+                let body = world.physics_world.get_body(*body).unwrap();
+                (
+                    Vec2::from(body.position().translation),
+                    prop.radius * 2.0 + LAZY_PLAYER_RECT,
+                )
+            }),
+    );
     for (entity, (lazy_collider, handle, thing_draw)) in world
         .entities
         .query::<(
@@ -652,14 +937,24 @@ fn update_lazy_collider(world: &mut World) {
         )>()
         .iter()
     {
-        let player_pos = get_player_body(world).position();
-        let player_rect = Rect {
-            x: player_pos.translation.vector.x - LAZY_PLAYER_RECT / 2.0,
-            y: player_pos.translation.vector.y - LAZY_PLAYER_RECT / 2.0,
-            w: LAZY_PLAYER_RECT,
-            h: LAZY_PLAYER_RECT,
-        };
-        let overlaps = player_rect.overlaps(&lazy_collider.rect);
+        // check the loaders against the rect in the pose the body had when the rect was made
+        let body = world
+            .physics_world
+            .get_body(lazy_collider.body_handle)
+            .unwrap();
+        let overlaps = loaders.iter().any(|&(pos, size)| {
+            let pos = lazy_collider.rest
+                * body
+                    .position()
+                    .inverse_transform_point(&point![pos.x, pos.y]);
+            let rect = Rect {
+                x: pos.x - size / 2.0,
+                y: pos.y - size / 2.0,
+                w: size,
+                h: size,
+            };
+            rect.overlaps(&lazy_collider.rect)
+        });
         let texture = thing_draw.map(|x| x.texture.as_str()).unwrap_or("none");
         if let Some(handle) = handle {
             if !overlaps {
@@ -710,28 +1005,40 @@ fn update_light(world: &mut World) {
         for (light, light_state) in light_group.lights.iter_mut() {
             let pos = pos + light.pos.rotate(Vec2::from_angle(angle));
             match light_state {
-                LightState::Flicker(flicker) => flicker.update(get_frame_time()),
+                LightState::Flicker(flicker) => flicker.update(get_frame_time(), light),
                 LightState::Ripple(ripple) => ripple.update(get_frame_time(), pos, &ripples),
             }
         }
     }
 }
-fn update_back(world: &mut World, assets: &Assets) {
-    world.back.update(get_frame_time());
+fn update_speedrun(world: &mut World) {
+    let dt = world.physics_world.integration_parameters.dt;
+    let alive = world.player_alive();
+    world.speedrun.tick(dt, alive, world.back.target);
+}
+fn update_ghost(world: &mut World) {
+    if !world.speedrun.started {
+        return;
+    }
+    let pose = draw::player::pose(world);
+    world.ghost.record(world.speedrun.time, pose);
+    while let Some(split) = world.speedrun.splits.get(world.ghost.splits()) {
+        world
+            .ghost
+            .split(split.level, split.time, world.speedrun.ranked);
+    }
+}
+fn update_current_level(world: &mut World, assets: &Assets) {
     let player_pos = Vec2::from(get_player_body(world).position().translation);
-    let current_level = world
-        .levels
-        .iter()
-        .filter(|(level_id, level_pos)| {
-            let dims = assets.levels[&level_id.0].0.dims;
-            player_pos.x > level_pos.x
-                && player_pos.x < level_pos.x + dims.x
-                && player_pos.y > level_pos.y
-                && player_pos.y < level_pos.y + dims.y
-        })
-        .map(|(level_id, _)| *level_id)
-        .next();
-    if let Some(level) = current_level {
-        world.back.set_target(level);
+    if let Some(level) = level_at(assets, world, player_pos) {
+        let pos = world.levels[&level];
+        let dims = assets.levels[&level.0].0.dims;
+        world.current_level = level;
+        world.current_bounds = Rect::new(pos.x, pos.y, dims.x, dims.y);
+        world.reached.insert(level);
     }
 }
+fn update_back(world: &mut World) {
+    world.back.update(get_frame_time());
+    world.back.set_target(world.current_level);
+}
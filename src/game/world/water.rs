@@ -0,0 +1,169 @@
+use itertools::Itertools;
+use macroquad::prelude::*;
+
+use crate::consts::*;
+
+use super::draw::floor::LiquidDraw;
+
+#[derive(Debug, Clone)]
+pub struct SurfaceColumn {
+    /// index into the vertices of the `LiquidDraw` this column displaces
+    pub vertex: usize,
+    pub rest: Vec2,
+    pub offset: f32,
+    pub vel: f32,
+}
+
+/// Spring chain simulated along the top edge of a water polygon.
+#[derive(Debug, Clone)]
+pub struct WaterSurface {
+    pub columns: Vec<SurfaceColumn>,
+    player_inside: bool,
+}
+
+impl WaterSurface {
+    pub fn new(liquid_draw: &LiquidDraw) -> Self {
+        let columns = liquid_draw
+            .surface
+            .iter()
+            .map(|&vertex| SurfaceColumn {
+                vertex,
+                rest: liquid_draw.vertices[vertex],
+                offset: 0.0,
+                vel: 0.0,
+            })
+            .collect();
+        Self {
+            columns,
+            player_inside: false,
+        }
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        let steps = (dt / WATER_SUBSTEP).ceil().max(1.0) as usize;
+        let dt = dt / steps as f32;
+        for _ in 0..steps {
+            let spread = (0..self.columns.len())
+                .map(|i| {
+                    let offset = self.columns[i].offset;
+                    let left = i.checked_sub(1).map_or(offset, |i| self.columns[i].offset);
+                    let right = self.columns.get(i + 1).map_or(offset, |c| c.offset);
+                    WATER_SPREAD * (left + right - 2.0 * offset)
+                })
+                .collect::<Vec<_>>();
+            for (column, spread) in self.columns.iter_mut().zip(spread) {
+                let spring = -WATER_SPRING_STIFFNESS * column.offset;
+                let damping = -WATER_SPRING_DAMPING * column.vel;
+                column.vel += (spring + damping + spread) * dt;
+                column.offset += column.vel * dt;
+            }
+        }
+    }
+
+    /// Pushes the columns around `x` with a velocity that falls off linearly over `radius`.
+    pub fn disturb(&mut self, x: f32, radius: f32, vel: f32) {
+        for column in self.columns.iter_mut() {
+            let distance = (column.rest.x - x).abs();
+            if distance < radius {
+                column.vel += vel * (1.0 - distance / radius);
+            }
+        }
+    }
+
+    /// Splashes when the player crosses the surface and leaves a wake while moving just under it.
+    pub fn interact(&mut self, pos: Vec2, vel: Vec2, dt: f32) {
+        let Some(height) = self.height_at(pos.x) else {
+            self.player_inside = false;
+            return;
+        };
+        let depth = pos.y - height;
+        if depth.abs() > WATER_SPLASH_RADIUS {
+            self.player_inside = depth > 0.0;
+            return;
+        }
+        let inside = depth > 0.0;
+        if inside != self.player_inside {
+            self.disturb(pos.x, WATER_SPLASH_RADIUS, vel.y * WATER_SPLASH_STRENGTH);
+        } else if inside {
+            self.disturb(
+                pos.x,
+                WATER_SPLASH_RADIUS,
+                vel.x.abs() * WATER_WAKE_STRENGTH * dt,
+            );
+        }
+        self.player_inside = inside;
+    }
+
+    /// Height of the displaced surface at `x`, if `x` is above this surface.
+    pub fn height_at(&self, x: f32) -> Option<f32> {
+        self.points().tuple_windows().find_map(|(a, b)| {
+            let (left, right) = if a.x < b.x { (a, b) } else { (b, a) };
+            if x < left.x || x > right.x {
+                return None;
+            }
+            let t = (x - left.x) / (right.x - left.x).max(f32::EPSILON);
+            Some(left.y + (right.y - left.y) * t)
+        })
+    }
+
+    pub fn points(&self) -> impl Iterator<Item = Vec2> + '_ {
+        self.columns
+            .iter()
+            .map(|column| column.rest + vec2(0.0, column.offset))
+    }
+
+    /// Copies `vertices` with the surface columns displaced.
+    pub fn displace(&self, vertices: &[Vec2]) -> Vec<Vec2> {
+        let mut vertices = vertices.to_vec();
+        for column in self.columns.iter() {
+            vertices[column.vertex].y = column.rest.y + column.offset;
+        }
+        vertices
+    }
+}
+
+/// Subdivides the longest run of edges along the top of the polygon, so it can be
+/// displaced as a wave. Returns the new vertices and the indices of the surface vertices
+/// in order along the run.
+pub fn subdivide_surface(vertices: &[Vec2]) -> (Vec<Vec2>, Vec<usize>) {
+    let n = vertices.len();
+    let min_y = vertices.iter().map(|v| v.y).fold(f32::INFINITY, f32::min);
+    let on_surface = |v: Vec2| v.y - min_y < WATER_SURFACE_TOLERANCE;
+    let is_surface_edge = |i: usize| on_surface(vertices[i]) && on_surface(vertices[(i + 1) % n]);
+
+    let mut best = (0, 0);
+    for start in 0..n {
+        if !is_surface_edge(start) || is_surface_edge((start + n - 1) % n) {
+            continue;
+        }
+        let len = (0..n)
+            .take_while(|j| is_surface_edge((start + j) % n))
+            .count();
+        if len > best.1 {
+            best = (start, len);
+        }
+    }
+    let (start, len) = best;
+    if len == 0 {
+        return (vertices.to_vec(), Vec::new());
+    }
+
+    let mut subdivided = Vec::new();
+    let mut surface = Vec::new();
+    for k in 0..n {
+        let v1 = vertices[(start + k) % n];
+        if k <= len {
+            surface.push(subdivided.len());
+        }
+        subdivided.push(v1);
+        if k < len {
+            let v2 = vertices[(start + k + 1) % n];
+            let count = ((v2 - v1).length() / WATER_SURFACE_SPACING).floor() as usize;
+            for i in 1..count {
+                surface.push(subdivided.len());
+                subdivided.push(v1.lerp(v2, i as f32 / count as f32));
+            }
+        }
+    }
+    (subdivided, surface)
+}
@@ -0,0 +1,125 @@
+use macroquad::prelude::*;
+
+use crate::consts::*;
+
+use super::draw::pixel_to_meter;
+use super::svg::{SvgItem, SvgShape};
+
+#[derive(Debug, Clone)]
+pub struct WindParticle {
+    pub pos: Vec2,
+    pub age: f32,
+    pub life: f32,
+    /// leaves tumble, streaks don't
+    pub leaf: bool,
+    pub spin: f32,
+}
+
+impl WindParticle {
+    /// 0 when spawned or dying, 1 halfway through its life
+    pub fn alpha(&self) -> f32 {
+        (self.age / self.life * std::f32::consts::PI).sin()
+    }
+}
+
+/// Area that pushes the player along `dir`.
+///
+/// Authored as a rect or path filled with the wind color, with `data-angle` (degrees,
+/// clockwise from right, default up) and `data-strength` (px/s², default 600).
+#[derive(Debug, Clone)]
+pub struct Wind {
+    pub vertices: Vec<Vec2>,
+    pub bounds: Rect,
+    pub dir: Vec2,
+    pub strength: f32,
+    pub particles: Vec<WindParticle>,
+    spawn_timer: f32,
+}
+
+impl Wind {
+    pub fn from_svg_item(item: &SvgItem, pos: Vec2) -> Option<Self> {
+        if item.color != WIND_COLOR {
+            return None;
+        }
+        let vertices = match &item.shape {
+            SvgShape::Rect(rect) => {
+                let half = rect.dims / 2.0;
+                let rotation = Vec2::from_angle(rect.rotate);
+                [
+                    vec2(-1.0, -1.0),
+                    vec2(1.0, -1.0),
+                    vec2(1.0, 1.0),
+                    vec2(-1.0, 1.0),
+                ]
+                .into_iter()
+                .map(|corner| rect.pos + rotation.rotate(corner * half))
+                .collect()
+            }
+            SvgShape::Path(path) => path.vertices.clone(),
+            SvgShape::Circle(_) => panic!("wind must be a rect or path"),
+        };
+        let vertices: Vec<Vec2> = vertices.into_iter().map(|v| v + pos).collect();
+        let min = vertices.iter().copied().reduce(Vec2::min).unwrap();
+        let max = vertices.iter().copied().reduce(Vec2::max).unwrap();
+        let angle = item.data_f32("angle").unwrap_or(-90.0).to_radians();
+        Some(Self {
+            vertices,
+            bounds: Rect::new(min.x, min.y, max.x - min.x, max.y - min.y),
+            dir: Vec2::from_angle(angle),
+            strength: pixel_to_meter(item.data_f32("strength").unwrap_or(600.0)),
+            particles: Vec::new(),
+            spawn_timer: 0.0,
+        })
+    }
+
+    pub fn contains(&self, point: Vec2) -> bool {
+        if !self.bounds.contains(point) {
+            return false;
+        }
+        // even-odd ray cast
+        let mut inside = false;
+        let n = self.vertices.len();
+        for i in 0..n {
+            let (a, b) = (self.vertices[i], self.vertices[(i + 1) % n]);
+            if (a.y > point.y) != (b.y > point.y)
+                && point.x < a.x + (point.y - a.y) / (b.y - a.y) * (b.x - a.x)
+            {
+                inside = !inside;
+            }
+        }
+        inside
+    }
+
+    pub fn update_particles(&mut self, dt: f32) {
+        let speed = self.strength * WIND_PARTICLE_SPEED;
+        for particle in self.particles.iter_mut() {
+            particle.age += dt;
+            particle.pos += self.dir * speed * dt;
+            particle.spin += dt * WIND_LEAF_SPIN;
+        }
+        let mut particles = std::mem::take(&mut self.particles);
+        particles.retain(|particle| particle.age < particle.life && self.contains(particle.pos));
+        self.particles = particles;
+
+        // spawn rate scales with the area, so big currents aren't sparse
+        let area = self.bounds.w * self.bounds.h;
+        self.spawn_timer += dt * area * WIND_PARTICLE_DENSITY;
+        while self.spawn_timer >= 1.0 {
+            self.spawn_timer -= 1.0;
+            let pos = vec2(
+                rand::gen_range(self.bounds.left(), self.bounds.right()),
+                rand::gen_range(self.bounds.top(), self.bounds.bottom()),
+            );
+            if !self.contains(pos) {
+                continue;
+            }
+            self.particles.push(WindParticle {
+                pos,
+                age: 0.0,
+                life: rand::gen_range(0.5, 1.5),
+                leaf: rand::gen_range(0.0, 1.0) < WIND_LEAF_CHANCE,
+                spin: rand::gen_range(0.0, std::f32::consts::TAU),
+            });
+        }
+    }
+}
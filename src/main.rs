@@ -29,6 +29,17 @@ fn get_config() -> GameConfig {
 #[macroquad::main(window_conf)]
 async fn main() {
     let mut game = Game::new(get_config()).await;
+    #[cfg(not(target_arch = "wasm32"))]
+    if let Some(dir) = &game.config.export_levels {
+        let scale = game.config.export_scale.unwrap_or(0.25);
+        game::export_levels(&game.assets, dir, scale);
+        return;
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    if game.config.compile_levels {
+        game::compile_levels(&game.assets);
+        return;
+    }
     loop {
         tick(&mut game).await;
         next_frame().await;
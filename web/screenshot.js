@@ -0,0 +1,25 @@
+screenshot_register_js_plugin = function (importObject) {
+  // pixels are rgba, top row first
+  importObject.env.screenshot_download = function (pixels, len, width, height) {
+    var bytes = new Uint8ClampedArray(wasm_memory.buffer, pixels, len);
+    var canvas = document.createElement('canvas');
+    canvas.width = width;
+    canvas.height = height;
+    canvas.getContext('2d').putImageData(new ImageData(new Uint8ClampedArray(bytes), width, height), 0, 0);
+    canvas.toBlob(function (blob) {
+      var link = document.createElement('a');
+      link.href = URL.createObjectURL(blob);
+      link.download = 'rolly-polly-' + Date.now() + '.png';
+      link.click();
+      setTimeout(function () {
+        URL.revokeObjectURL(link.href);
+      }, 1000);
+    });
+  };
+};
+
+miniquad_add_plugin({
+  register_plugin: screenshot_register_js_plugin,
+  name: 'screenshot',
+  version: '0.1.0',
+});